@@ -0,0 +1,57 @@
+//! Measures repeated access to the same type through `Workspace::get_or_default`, which is the
+//! pattern the hit path of the workspace lookup is optimized for.
+//!
+//! Run with `cargo bench --bench repeated_access`. To evaluate a change to the lookup, compare
+//! the output against a run on the parent commit.
+
+use davenport::Workspace;
+use std::hint::black_box;
+use std::time::Instant;
+
+const ACCESSES: usize = 10_000_000;
+
+fn repeated_same_type(ws: &mut Workspace) -> u64 {
+    let mut sum = 0;
+    for i in 0..ACCESSES {
+        let buffer = ws.get_or_default::<Vec<u64>>();
+        buffer.clear();
+        buffer.push(black_box(i as u64));
+        sum += buffer[0];
+    }
+    sum
+}
+
+fn alternating_types(ws: &mut Workspace) -> u64 {
+    let mut sum = 0;
+    for i in 0..ACCESSES / 2 {
+        let a = ws.get_or_default::<Vec<u64>>();
+        a.clear();
+        a.push(black_box(i as u64));
+        sum += a[0];
+        let b = ws.get_or_default::<Vec<u32>>();
+        b.clear();
+        b.push(black_box(i as u32));
+        sum += u64::from(b[0]);
+    }
+    sum
+}
+
+fn measure(name: &str, f: fn(&mut Workspace) -> u64) {
+    let mut ws = Workspace::new();
+    // Populate the workspace with a few other types, so that lookups are not trivial
+    ws.get_or_default::<Vec<u8>>();
+    ws.get_or_default::<Vec<f32>>();
+    ws.get_or_default::<String>();
+    // Warm up, so that all objects are created before measuring
+    black_box(f(&mut ws));
+    let start = Instant::now();
+    black_box(f(&mut ws));
+    let elapsed = start.elapsed();
+    let per_access = elapsed / ACCESSES as u32;
+    println!("{name:>12}: {elapsed:?} total, {per_access:?} per access");
+}
+
+fn main() {
+    measure("same type", repeated_same_type);
+    measure("alternating", alternating_types);
+}
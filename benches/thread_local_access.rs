@@ -0,0 +1,63 @@
+//! Compares accessing a thread-local workspace once per operation with accessing it once
+//! for a batch of operations through `with_thread_local_workspace_raw`.
+//!
+//! Run with `cargo bench --bench thread_local_access`.
+
+use davenport::{
+    define_thread_local_workspace, with_thread_local_workspace, with_thread_local_workspace_raw,
+};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+define_thread_local_workspace!(WORKSPACE);
+
+const OPS: usize = 1_000;
+const ROUNDS: usize = 1_000;
+
+fn per_op() -> u64 {
+    let mut sum = 0;
+    for i in 0..OPS {
+        sum += with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u64>| {
+            buffer.clear();
+            buffer.push(black_box(i as u64));
+            buffer[0]
+        });
+    }
+    sum
+}
+
+fn batched() -> u64 {
+    with_thread_local_workspace_raw(&WORKSPACE, |ws| {
+        let handle = ws.handle::<Vec<u64>>();
+        let mut sum = 0;
+        for i in 0..OPS {
+            let buffer = handle.get(ws).unwrap();
+            buffer.clear();
+            buffer.push(black_box(i as u64));
+            sum += buffer[0];
+        }
+        sum
+    })
+}
+
+fn measure(name: &str, f: fn() -> u64) -> Duration {
+    // Warm up, so that the buffer is allocated before measuring
+    black_box(f());
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f());
+    }
+    let elapsed = start.elapsed();
+    let per_op = elapsed / (ROUNDS * OPS) as u32;
+    println!("{name:>8}: {elapsed:?} total, {per_op:?} per operation");
+    elapsed
+}
+
+fn main() {
+    let per_op = measure("per-op", per_op);
+    let batched = measure("batched", batched);
+    println!(
+        "time per-op / batched: {:.2}",
+        per_op.as_secs_f64() / batched.as_secs_f64()
+    );
+}
@@ -0,0 +1,138 @@
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::ptr::NonNull;
+
+/// A reusable buffer whose contents are aligned to `ALIGN` bytes.
+///
+/// A `Vec<T>` only guarantees the alignment of `T`, which is insufficient for e.g. aligned SIMD
+/// loads. `AlignedScratch` instead allocates its buffer with an alignment of at least `ALIGN`
+/// bytes (or the alignment of `T`, if larger). `ALIGN` must be a power of two.
+///
+/// The buffer is intended to be stored in a [`Workspace`](crate::Workspace), and the allocation
+/// is reused across calls to [`AlignedScratch::prepare`]. Whenever the buffer needs to grow,
+/// a new buffer with the same alignment guarantee is allocated, so alignment is never lost
+/// through reuse.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_thread_local_workspace, AlignedScratch};
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// fn sum(values: &[f32]) -> f32 {
+///     with_thread_local_workspace(&WORKSPACE, |scratch: &mut AlignedScratch<f32, 64>| {
+///         let buffer = scratch.prepare(values.len());
+///         assert_eq!(buffer.as_ptr() as usize % 64, 0);
+///         buffer.copy_from_slice(values);
+///         buffer.iter().sum()
+///     })
+/// }
+///
+/// assert_eq!(sum(&[1.0, 2.0, 3.0]), 6.0);
+/// ```
+pub struct AlignedScratch<T, const ALIGN: usize> {
+    ptr: NonNull<T>,
+    len: usize,
+    capacity: usize,
+}
+
+impl<T: Copy + Default, const ALIGN: usize> AlignedScratch<T, ALIGN> {
+    const VALID_ALIGN: () = assert!(ALIGN.is_power_of_two(), "ALIGN must be a power of two");
+
+    /// Creates a new, empty buffer. Does not allocate.
+    pub fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_ALIGN;
+        Self {
+            ptr: Self::dangling(),
+            len: 0,
+            capacity: 0,
+        }
+    }
+
+    /// Returns an aligned slice of the given length.
+    ///
+    /// Elements that were present after the previous call are preserved, similar to
+    /// [`Vec::resize`], and any additional elements are set to `T::default()`. The buffer
+    /// is only reallocated if `len` exceeds the current capacity.
+    pub fn prepare(&mut self, len: usize) -> &mut [T] {
+        if len > self.capacity {
+            self.grow(len.max(self.capacity.saturating_mul(2)));
+        }
+        for i in self.len..len {
+            // SAFETY: i < capacity, and T: Copy so overwriting requires no drop
+            unsafe { self.ptr.as_ptr().add(i).write(T::default()) };
+        }
+        self.len = len;
+        // SAFETY: The first `len` elements are initialized and the pointer is valid for
+        // at least `capacity >= len` elements
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), len) }
+    }
+
+    /// The number of elements the buffer can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn grow(&mut self, new_capacity: usize) {
+        let new_layout = Self::layout(new_capacity);
+        let new_ptr = if new_layout.size() == 0 {
+            Self::dangling()
+        } else {
+            // SAFETY: The layout has non-zero size
+            let ptr = unsafe { alloc::alloc(new_layout) } as *mut T;
+            let ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(new_layout));
+            // SAFETY: Both buffers are valid for `len` elements and do not overlap
+            unsafe { std::ptr::copy_nonoverlapping(self.ptr.as_ptr(), ptr.as_ptr(), self.len) };
+            ptr
+        };
+        self.deallocate();
+        self.ptr = new_ptr;
+        self.capacity = new_capacity;
+    }
+}
+
+impl<T, const ALIGN: usize> AlignedScratch<T, ALIGN> {
+    fn layout(capacity: usize) -> Layout {
+        let align = ALIGN.max(std::mem::align_of::<T>());
+        let size = std::mem::size_of::<T>()
+            .checked_mul(capacity)
+            .expect("Capacity overflow");
+        Layout::from_size_align(size, align).expect("Invalid layout for aligned buffer")
+    }
+
+    fn dangling() -> NonNull<T> {
+        let align = ALIGN.max(std::mem::align_of::<T>());
+        // SAFETY: `align` is a power of two and therefore non-zero
+        unsafe { NonNull::new_unchecked(std::ptr::without_provenance_mut(align)) }
+    }
+
+    fn deallocate(&mut self) {
+        let layout = Self::layout(self.capacity);
+        if layout.size() != 0 {
+            // SAFETY: The buffer was allocated with this exact layout
+            unsafe { alloc::dealloc(self.ptr.as_ptr() as *mut u8, layout) };
+        }
+    }
+}
+
+impl<T: Copy + Default, const ALIGN: usize> Default for AlignedScratch<T, ALIGN> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const ALIGN: usize> Drop for AlignedScratch<T, ALIGN> {
+    fn drop(&mut self) {
+        self.deallocate();
+    }
+}
+
+impl<T, const ALIGN: usize> fmt::Debug for AlignedScratch<T, ALIGN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AlignedScratch")
+            .field("len", &self.len)
+            .field("capacity", &self.capacity)
+            .field("align", &ALIGN)
+            .finish()
+    }
+}
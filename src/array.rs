@@ -0,0 +1,86 @@
+use crate::{CapacityReport, Shrinkable, WorkspaceMemoryUsage};
+use ndarray::ArrayViewMut2;
+use std::mem::size_of;
+
+/// A reusable buffer for two-dimensional `ndarray` scratch arrays.
+///
+/// Storing an `Array2<T>` directly in a [`Workspace`](crate::Workspace) means that a new array
+/// must be allocated whenever the required shape changes. `ScratchArray2` instead keeps a flat
+/// backing buffer and hands out views of the requested shape with [`ScratchArray2::prepare`].
+///
+/// The backing buffer is only reallocated when a shape with more elements than ever before is
+/// requested. Smaller shapes reuse the existing allocation, and `prepare` never shrinks it.
+/// If the memory retained after a single large use is a concern, attach
+/// [`MemoryPolicy::ShrinkAfterUse`](crate::MemoryPolicy::ShrinkAfterUse) to the object with
+/// [`Workspace::get_or_default_with_policy`](crate::Workspace::get_or_default_with_policy).
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_thread_local_workspace, ScratchArray2};
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// fn trace_of_square(n: usize) -> f64 {
+///     with_thread_local_workspace(&WORKSPACE, |scratch: &mut ScratchArray2<f64>| {
+///         let mut matrix = scratch.prepare((n, n));
+///         matrix.fill(0.0);
+///         matrix.diag_mut().fill(2.0);
+///         matrix.dot(&matrix).diag().sum()
+///     })
+/// }
+///
+/// assert_eq!(trace_of_square(3), 12.0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ScratchArray2<T> {
+    data: Vec<T>,
+}
+
+impl<T: Clone + Default> ScratchArray2<T> {
+    /// Creates a new, empty buffer. Does not allocate.
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+
+    /// Returns a mutable view of the given shape, in standard (row-major) layout.
+    ///
+    /// Like [`AlignedScratch::prepare`](crate::AlignedScratch::prepare), the elements of the
+    /// flat backing buffer are preserved between calls and any additional elements are set to
+    /// `T::default()`. When the shape changes, the preserved elements are reinterpreted in the
+    /// new shape, so callers should generally treat the contents as unspecified and initialize
+    /// the view themselves.
+    pub fn prepare(&mut self, shape: (usize, usize)) -> ArrayViewMut2<'_, T> {
+        let len = shape
+            .0
+            .checked_mul(shape.1)
+            .expect("Shape of scratch array overflows usize");
+        self.data.resize(len, T::default());
+        ArrayViewMut2::from_shape(shape, &mut self.data)
+            .expect("Internal error: Backing buffer always has exactly the required length")
+    }
+}
+
+impl<T> CapacityReport for ScratchArray2<T> {
+    fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+}
+
+impl<T> Shrinkable for ScratchArray2<T> {
+    fn used_len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn shrink_to(&mut self, min_capacity: usize) {
+        self.data.shrink_to(min_capacity)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.data.truncate(len)
+    }
+}
+
+impl<T> WorkspaceMemoryUsage for ScratchArray2<T> {
+    fn memory_usage(&self) -> usize {
+        self.data.capacity() * size_of::<T>()
+    }
+}
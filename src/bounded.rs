@@ -0,0 +1,138 @@
+use crate::Workspace;
+use std::error::Error;
+use std::fmt;
+
+/// A [`Workspace`] that holds objects of at most `MAX` distinct types.
+///
+/// Every distinct type stored in a workspace keeps its own allocation alive, so bounding the
+/// number of types bounds the memory fan-out of a workspace. Unlike eviction under a memory
+/// budget, exceeding the bound is a hard error: inserting an object of a new type into a full
+/// workspace fails, while objects already present remain accessible.
+///
+/// ```rust
+/// use davenport::BoundedWorkspace;
+///
+/// let mut ws = BoundedWorkspace::<2>::new();
+/// ws.get_or_default::<Vec<u32>>().push(1);
+/// ws.get_or_default::<Vec<f64>>().push(2.0);
+/// assert!(ws.try_get_or_default::<String>().is_err());
+/// assert_eq!(ws.get_or_default::<Vec<u32>>(), &[1]);
+/// ```
+#[derive(Debug, Default)]
+pub struct BoundedWorkspace<const MAX: usize> {
+    workspace: Workspace,
+}
+
+impl<const MAX: usize> BoundedWorkspace<MAX> {
+    /// Creates a new, empty workspace.
+    pub const fn new() -> Self {
+        Self {
+            workspace: Workspace::new(),
+        }
+    }
+
+    /// Returns the object of type `W`, inserting the object returned by `create` if no object
+    /// of type `W` is present.
+    ///
+    /// Returns an error if the object is not present and the workspace already holds objects of
+    /// `MAX` distinct types. In this case, `create` is not called.
+    pub fn try_get_or_insert_with<W, F>(&mut self, create: F) -> Result<&mut W, TypeLimitExceeded>
+    where
+        W: 'static,
+        F: FnOnce() -> W,
+    {
+        if self.workspace.find_index_of::<W>().is_none() && self.len() >= MAX {
+            Err(TypeLimitExceeded {
+                max: MAX,
+                type_name: std::any::type_name::<W>(),
+            })
+        } else {
+            Ok(self.workspace.get_or_insert_with(create))
+        }
+    }
+
+    /// Same as [`BoundedWorkspace::try_get_or_insert_with`], but inserts a default-constructed
+    /// object.
+    pub fn try_get_or_default<W>(&mut self) -> Result<&mut W, TypeLimitExceeded>
+    where
+        W: 'static + Default,
+    {
+        self.try_get_or_insert_with(W::default)
+    }
+
+    /// Returns the object of type `W`, inserting a default-constructed object if not present.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the object is not present and the workspace already holds objects of `MAX`
+    /// distinct types.
+    pub fn get_or_default<W>(&mut self) -> &mut W
+    where
+        W: 'static + Default,
+    {
+        match self.try_get_or_default() {
+            Ok(w) => w,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    pub fn try_get<W: 'static>(&self) -> Option<&W> {
+        self.workspace.try_get()
+    }
+
+    pub fn try_get_mut<W: 'static>(&mut self) -> Option<&mut W> {
+        self.workspace.try_get_mut()
+    }
+
+    /// Returns the number of distinct types currently stored.
+    pub fn len(&self) -> usize {
+        self.workspace.workspaces.len()
+    }
+
+    /// Returns `true` if no objects are stored.
+    pub fn is_empty(&self) -> bool {
+        self.workspace.workspaces.is_empty()
+    }
+
+    /// Returns the underlying workspace for read-only inspection.
+    pub fn workspace(&self) -> &Workspace {
+        &self.workspace
+    }
+
+    /// Removes all objects from the workspace, see [`Workspace::clear`].
+    pub fn clear(&mut self) {
+        self.workspace.clear();
+    }
+}
+
+/// Error returned when inserting an object of a new type into a full [`BoundedWorkspace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeLimitExceeded {
+    max: usize,
+    type_name: &'static str,
+}
+
+impl TypeLimitExceeded {
+    /// The maximum number of distinct types of the workspace.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// The name of the type that could not be inserted.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+impl fmt::Display for TypeLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "can not insert an object of type {} into a workspace that already holds \
+             objects of the maximum of {} distinct types",
+            self.type_name, self.max
+        )
+    }
+}
+
+impl Error for TypeLimitExceeded {}
@@ -0,0 +1,78 @@
+use crate::{with_borrowed_workspace, WorkspaceKey};
+use bumpalo::Bump;
+use std::fmt;
+
+/// A reusable arena for many small scratch allocations, backed by [`bumpalo::Bump`].
+///
+/// A single `Vec` is a poor fit for code that allocates many small, differently typed scratch
+/// objects per call. `BumpScratch` instead provides an arena in which such objects are
+/// allocated cheaply, and freed all at once with [`BumpScratch::reset`].
+///
+/// Resetting retains the largest chunk of memory allocated by the arena so far, and releases
+/// the others. After a few uses, the arena therefore typically serves all allocations from
+/// a single retained chunk without touching the global allocator.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_thread_local_bump};
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// let total = with_thread_local_bump(&WORKSPACE, |bump| {
+///     let values = bump.alloc_slice_fill_copy(4, 2u32);
+///     let label = bump.alloc_str("scratch");
+///     values.iter().sum::<u32>() as usize + label.len()
+/// });
+/// assert_eq!(total, 15);
+/// ```
+#[derive(Default)]
+pub struct BumpScratch {
+    bump: Bump,
+}
+
+impl BumpScratch {
+    /// Creates a new, empty arena. Does not allocate.
+    pub fn new() -> Self {
+        Self { bump: Bump::new() }
+    }
+
+    /// Returns the arena for allocation.
+    pub fn bump(&self) -> &Bump {
+        &self.bump
+    }
+
+    /// Frees all allocations made in the arena, retaining its largest chunk of memory.
+    pub fn reset(&mut self) {
+        self.bump.reset();
+    }
+
+    /// The total number of bytes held by the arena, including unused capacity.
+    pub fn allocated_bytes(&self) -> usize {
+        self.bump.allocated_bytes()
+    }
+}
+
+impl fmt::Debug for BumpScratch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BumpScratch")
+            .field("allocated_bytes", &self.allocated_bytes())
+            .finish()
+    }
+}
+
+/// Runs the provided closure with the arena of a thread-local [`BumpScratch`], and resets the
+/// arena after the closure returns.
+///
+/// Since the arena is reset afterwards, nothing allocated in the arena can be returned from the
+/// closure.
+///
+/// ## Panics
+///
+/// Panics if used recursively with the same workspace variable.
+pub fn with_thread_local_bump<T>(workspace: impl WorkspaceKey, f: impl FnOnce(&Bump) -> T) -> T {
+    with_borrowed_workspace(workspace, |type_erased_workspace| {
+        let scratch = type_erased_workspace.get_or_default::<BumpScratch>();
+        let result = f(scratch.bump());
+        scratch.reset();
+        result
+    })
+}
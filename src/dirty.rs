@@ -0,0 +1,112 @@
+use crate::{with_borrowed_workspace, WorkspaceKey};
+use std::ops::{Deref, DerefMut};
+
+/// A wrapper that records whether the wrapped object has been mutably accessed.
+///
+/// Any mutable access, whether through [`DirtyTracked::get_mut`] or [`DerefMut`], marks the
+/// object as dirty. The flag is conservative: it records that the object *may* have been
+/// modified, not that its value actually changed. This is useful for caching layers that must
+/// decide whether a buffer needs to be persisted, see [`with_thread_local_workspace_tracked`].
+///
+/// ```rust
+/// use davenport::DirtyTracked;
+///
+/// let mut tracked = DirtyTracked::new(vec![1, 2]);
+/// assert_eq!(tracked.len(), 2);
+/// assert!(!tracked.is_dirty());
+/// tracked.push(3);
+/// assert!(tracked.is_dirty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DirtyTracked<W> {
+    value: W,
+    dirty: bool,
+}
+
+impl<W> DirtyTracked<W> {
+    /// Wraps the given object. The wrapper starts out clean.
+    pub fn new(value: W) -> Self {
+        Self {
+            value,
+            dirty: false,
+        }
+    }
+
+    /// Returns a shared reference to the object, without marking it as dirty.
+    pub fn get(&self) -> &W {
+        &self.value
+    }
+
+    /// Returns a mutable reference to the object, and marks it as dirty.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.dirty = true;
+        &mut self.value
+    }
+
+    /// Returns `true` if the object has been mutably accessed since it was wrapped or since
+    /// the last call to [`DirtyTracked::clear_dirty`].
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Marks the object as clean.
+    pub fn clear_dirty(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Returns the wrapped object.
+    pub fn into_inner(self) -> W {
+        self.value
+    }
+}
+
+impl<W> Deref for DirtyTracked<W> {
+    type Target = W;
+
+    fn deref(&self) -> &W {
+        self.get()
+    }
+}
+
+impl<W> DerefMut for DirtyTracked<W> {
+    fn deref_mut(&mut self) -> &mut W {
+        self.get_mut()
+    }
+}
+
+/// Same as [`with_thread_local_workspace`](crate::with_thread_local_workspace), but passes the
+/// object wrapped in a [`DirtyTracked`], and additionally returns whether the closure
+/// mutably accessed it.
+///
+/// The dirty flag is cleared before the closure is called, so the returned flag only reflects
+/// accesses made by this closure.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_thread_local_workspace_tracked};
+/// use davenport::DirtyTracked;
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// let (len, dirty) =
+///     with_thread_local_workspace_tracked(&WORKSPACE, |cache: &mut DirtyTracked<Vec<u32>>| {
+///         cache.push(1);
+///         cache.len()
+///     });
+/// assert_eq!(len, 1);
+/// assert!(dirty);
+/// ```
+///
+/// ## Panics
+///
+/// Panics if used recursively with the same workspace variable.
+pub fn with_thread_local_workspace_tracked<W: 'static + Default, T>(
+    workspace: impl WorkspaceKey,
+    f: impl FnOnce(&mut DirtyTracked<W>) -> T,
+) -> (T, bool) {
+    with_borrowed_workspace(workspace, |type_erased_workspace| {
+        let tracked = type_erased_workspace.get_or_default::<DirtyTracked<W>>();
+        tracked.clear_dirty();
+        let result = f(tracked);
+        (result, tracked.is_dirty())
+    })
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+use std::mem::{discriminant, Discriminant};
+
+/// A set of reusable buffers of type `B`, with one buffer per variant of the enum `E`.
+///
+/// A [`Workspace`](crate::Workspace) selects objects by their static type. `EnumWorkspace`
+/// instead selects a buffer by the variant of a runtime value, which suits e.g. state
+/// machines that choose a scratch buffer depending on their current mode. Buffers are keyed
+/// by [`std::mem::discriminant`], so any data carried by the variant is ignored.
+///
+/// `EnumWorkspace` can itself be stored in a [`Workspace`](crate::Workspace).
+///
+/// ```rust
+/// use davenport::EnumWorkspace;
+///
+/// enum Mode {
+///     Coarse,
+///     Fine { level: u32 },
+/// }
+///
+/// let mut buffers = EnumWorkspace::<Mode, Vec<f64>>::new();
+/// buffers.get_or_default(&Mode::Coarse).push(1.0);
+/// buffers.get_or_default(&Mode::Fine { level: 1 }).push(2.0);
+/// assert_eq!(buffers.get_or_default(&Mode::Fine { level: 2 }), &[2.0]);
+/// ```
+pub struct EnumWorkspace<E, B> {
+    slots: Vec<(Discriminant<E>, B)>,
+}
+
+impl<E, B> EnumWorkspace<E, B> {
+    /// Creates a new, empty set of buffers.
+    pub const fn new() -> Self {
+        Self { slots: Vec::new() }
+    }
+
+    /// Returns the buffer for the variant of `kind`, inserting a default-constructed buffer
+    /// if it is not present.
+    pub fn get_or_default(&mut self, kind: &E) -> &mut B
+    where
+        B: Default,
+    {
+        let key = discriminant(kind);
+        let idx = match self.slots.iter().position(|(d, _)| *d == key) {
+            Some(idx) => idx,
+            None => {
+                self.slots.push((key, B::default()));
+                self.slots.len() - 1
+            }
+        };
+        &mut self.slots[idx].1
+    }
+
+    /// Returns the buffer for the variant of `kind`, if present.
+    pub fn try_get(&self, kind: &E) -> Option<&B> {
+        let key = discriminant(kind);
+        self.slots.iter().find(|(d, _)| *d == key).map(|(_, b)| b)
+    }
+
+    /// Returns the number of variants that currently have a buffer.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns `true` if no variant has a buffer.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+}
+
+impl<E, B> Default for EnumWorkspace<E, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E, B: fmt::Debug> fmt::Debug for EnumWorkspace<E, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.slots.iter().map(|(_, b)| b))
+            .finish()
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{
+    try_with_borrowed_workspace, FrozenWorkspaceError, TypeLimitExceeded, WorkspaceBusy,
+    WorkspaceKey,
+};
+use std::collections::TryReserveError;
+use thiserror::Error;
+
+/// A single error type covering the ways in which workspace access can fail.
+///
+/// The fallible functions of the crate return the error type most specific to them, such as
+/// [`FrozenWorkspaceError`], [`TypeLimitExceeded`] or [`TryReserveError`]. Each of these
+/// converts into a `WorkspaceError`, so that code using several fallible functions can propagate
+/// all of their errors with `?` and handle them in one place.
+///
+/// ```rust
+/// use davenport::{Workspace, WorkspaceError};
+///
+/// fn prepare(ws: &mut Workspace, len: usize) -> Result<(), WorkspaceError> {
+///     ws.try_get_or_default::<Vec<usize>>()?.clear();
+///     ws.try_get_slice_or_default::<f64>(len)?;
+///     Ok(())
+/// }
+///
+/// let mut ws = Workspace::default();
+/// ws.freeze();
+/// let err = prepare(&mut ws, 10).unwrap_err();
+/// assert!(matches!(err, WorkspaceError::Frozen { .. }));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum WorkspaceError {
+    /// A thread-local workspace variable was used while already in use on the same thread.
+    #[error("thread-local workspace is already in use on this thread")]
+    Reentrant,
+    /// A [`SharedWorkspace`](crate::SharedWorkspace) was already in use through another handle.
+    #[error("shared workspace is already in use")]
+    Contended,
+    /// The same type was requested more than once in a single mutable access.
+    #[error("can not mutably borrow an object of type {type_name} more than once")]
+    DuplicateType {
+        /// The name of the type that was requested more than once.
+        type_name: &'static str,
+    },
+    /// Memory for a workspace object could not be allocated.
+    #[error("failed to allocate memory for workspace object")]
+    AllocationFailed(#[from] TryReserveError),
+    /// An object of a new type was requested from a [frozen](crate::Workspace::freeze) workspace.
+    #[error("can not insert an object of type {type_name} into a frozen workspace")]
+    Frozen {
+        /// The name of the type that could not be inserted.
+        type_name: &'static str,
+    },
+    /// An object of a new type was requested from a full
+    /// [`BoundedWorkspace`](crate::BoundedWorkspace).
+    #[error(
+        "can not insert an object of type {type_name} into a workspace that already holds \
+         objects of the maximum of {max} distinct types"
+    )]
+    TypeLimit {
+        /// The maximum number of distinct types of the workspace.
+        max: usize,
+        /// The name of the type that could not be inserted.
+        type_name: &'static str,
+    },
+}
+
+impl From<FrozenWorkspaceError> for WorkspaceError {
+    fn from(err: FrozenWorkspaceError) -> Self {
+        Self::Frozen {
+            type_name: err.type_name(),
+        }
+    }
+}
+
+impl From<TypeLimitExceeded> for WorkspaceError {
+    fn from(err: TypeLimitExceeded) -> Self {
+        Self::TypeLimit {
+            max: err.max(),
+            type_name: err.type_name(),
+        }
+    }
+}
+
+impl From<WorkspaceBusy> for WorkspaceError {
+    fn from(_: WorkspaceBusy) -> Self {
+        Self::Contended
+    }
+}
+
+/// Same as [`with_thread_local_workspace`](crate::with_thread_local_workspace), but returns
+/// [`WorkspaceError::Reentrant`] instead of panicking if the workspace variable is already in
+/// use on this thread. As with `with_thread_local_workspace`, the memory policy of the object is
+/// applied after the closure returns.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, try_with_thread_local_workspace};
+/// use davenport::WorkspaceError;
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// let result = try_with_thread_local_workspace(&WORKSPACE, |_: &mut Vec<u32>| {
+///     try_with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| buffer.len())
+/// });
+/// assert_eq!(result, Ok(Err(WorkspaceError::Reentrant)));
+/// ```
+pub fn try_with_thread_local_workspace<W: 'static + Default, T>(
+    workspace: impl WorkspaceKey,
+    f: impl FnOnce(&mut W) -> T,
+) -> Result<T, WorkspaceError> {
+    try_with_borrowed_workspace(workspace, |type_erased_workspace| {
+        let result = f(type_erased_workspace.get_or_default());
+        type_erased_workspace.apply_memory_policy_of_last();
+        result
+    })
+}
@@ -0,0 +1,107 @@
+use crate::{with_thread_local_workspace, Workspace, WorkspaceKey};
+use std::cell::RefCell;
+#[cfg(all(debug_assertions, feature = "debug-global-thread"))]
+use std::{sync::OnceLock, thread::ThreadId};
+
+/// A workspace stored in a plain `static` rather than in thread-local storage.
+///
+/// On some platforms, accessing thread-local storage is comparatively expensive. In strictly
+/// single-threaded programs, `GlobalWorkspace` together with [`with_global_workspace`] provides
+/// the same functionality as [`with_thread_local_workspace`](crate::with_thread_local_workspace)
+/// without going through thread-local storage. Since `GlobalWorkspace` implements
+/// [`WorkspaceKey`], all other thread-local helpers accept it as well.
+///
+/// With the `wasm-single-thread` feature, [`define_thread_local_workspace`] defines a
+/// `GlobalWorkspace` on WebAssembly targets without the `atomics` target feature, which can not
+/// spawn threads, so that the contract below holds by construction.
+///
+/// [`define_thread_local_workspace`]: crate::define_thread_local_workspace
+///
+/// ## Safety
+///
+/// **`GlobalWorkspace` is only sound if it is accessed from a single thread for the entire
+/// duration of the program.** It implements [`Sync`] so that it can be stored in a `static`,
+/// but it provides no synchronization whatsoever. Accessing the same `GlobalWorkspace` from
+/// more than one thread is undefined behavior. This is why [`GlobalWorkspace::new`] is
+/// `unsafe`: by calling it, you promise to uphold this contract.
+///
+/// With the `debug-global-thread` feature, every access in debug builds checks that it occurs
+/// on the same thread as the first access, and panics otherwise. Since identifying the
+/// current thread itself goes through thread-local storage, the check is opt-in, and the
+/// `single-threaded` feature alone performs no check. The owning thread is recorded in a
+/// synchronized cell, so that the check itself is not a data race. It is a debugging aid and
+/// must not be relied upon for soundness.
+///
+/// ```rust
+/// use davenport::{with_global_workspace, GlobalWorkspace};
+///
+/// // SAFETY: This program only ever accesses WORKSPACE from the main thread
+/// static WORKSPACE: GlobalWorkspace = unsafe { GlobalWorkspace::new() };
+///
+/// let len = with_global_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| {
+///     buffer.push(1);
+///     buffer.len()
+/// });
+/// assert_eq!(len, 1);
+/// ```
+pub struct GlobalWorkspace {
+    workspace: RefCell<Workspace>,
+    #[cfg(all(debug_assertions, feature = "debug-global-thread"))]
+    owner: OnceLock<ThreadId>,
+}
+
+// SAFETY: The user promises to only ever access the workspace from a single thread
+// when constructing it through the unsafe `GlobalWorkspace::new`.
+unsafe impl Sync for GlobalWorkspace {}
+
+impl GlobalWorkspace {
+    /// Creates a new, empty global workspace.
+    ///
+    /// ## Safety
+    ///
+    /// The returned workspace must only ever be accessed from a single thread. See the
+    /// [type-level documentation](GlobalWorkspace) for details.
+    pub const unsafe fn new() -> Self {
+        Self {
+            workspace: RefCell::new(Workspace::new()),
+            #[cfg(all(debug_assertions, feature = "debug-global-thread"))]
+            owner: OnceLock::new(),
+        }
+    }
+
+    #[cfg(all(debug_assertions, feature = "debug-global-thread"))]
+    fn check_thread(&self) {
+        let current = std::thread::current().id();
+        let owner = *self.owner.get_or_init(|| current);
+        assert_eq!(
+            owner, current,
+            "GlobalWorkspace accessed from more than one thread. This is undefined behavior."
+        );
+    }
+}
+
+/// Runs the provided closure with the global workspace as an argument.
+///
+/// This mirrors [`with_thread_local_workspace`](crate::with_thread_local_workspace), but
+/// operates on a [`GlobalWorkspace`] instead of thread-local storage. See the documentation
+/// of [`GlobalWorkspace`] for the soundness contract.
+///
+/// ## Panics
+///
+/// Panics if used recursively with the same workspace variable, or, with the
+/// `debug-global-thread` feature in debug builds, if the workspace is accessed from more than
+/// one thread.
+pub fn with_global_workspace<W: 'static + Default, T>(
+    workspace: &'static GlobalWorkspace,
+    f: impl FnOnce(&mut W) -> T,
+) -> T {
+    with_thread_local_workspace(workspace, f)
+}
+
+impl WorkspaceKey for GlobalWorkspace {
+    fn with_cell<T>(&self, f: impl FnOnce(&RefCell<Workspace>) -> T) -> T {
+        #[cfg(all(debug_assertions, feature = "debug-global-thread"))]
+        self.check_thread();
+        f(&self.workspace)
+    }
+}
@@ -146,10 +146,83 @@
 //! across entire crates.
 //!
 
-use std::any::Any;
-use std::cell::RefCell;
+mod aligned;
+#[cfg(feature = "ndarray")]
+mod array;
+mod bounded;
+#[cfg(feature = "bumpalo")]
+mod bump;
+mod dirty;
+mod enum_workspace;
+mod error;
+#[cfg(feature = "single-threaded")]
+mod global;
+mod memory;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod poison;
+mod policy;
+mod pool;
+#[cfg(feature = "rayon")]
+mod rayon_pool;
+mod reentrant;
+#[cfg(feature = "rand")]
+mod rng;
+mod scoped;
+mod scratch;
+mod session;
+mod shared;
+mod slot;
+#[cfg(feature = "ttl")]
+mod ttl;
+
+pub use aligned::AlignedScratch;
+pub use bounded::{BoundedWorkspace, TypeLimitExceeded};
+pub use dirty::{with_thread_local_workspace_tracked, DirtyTracked};
+pub use enum_workspace::EnumWorkspace;
+pub use error::{try_with_thread_local_workspace, WorkspaceError};
+pub use memory::{AllocEvent, WorkspaceMemoryUsage};
+pub use poison::{with_thread_local_workspace_poisoned, Poison};
+pub use policy::{MemoryPolicy, Shrinkable};
+pub use pool::{
+    Clear, PhaseBuffer, PooledItem, Rented, ScratchAllocator, SharedCapacityPool, WorkspacePool,
+};
+pub use reentrant::{with_thread_local_reentrant_workspace, ReentrantWorkspace};
+pub use scoped::{scope_with_borrowed_workspace, ScopedWorkspace};
+pub use scratch::{RecyclingVecOfVecs, ScratchMap};
+pub use session::WorkspaceSession;
+pub use shared::{
+    SharedWorkspace, SharedWorkspaceError, WorkspaceBusy, WorkspaceGuard, WorkspaceReadGuard,
+};
+pub use slot::TraitSlot;
+
+#[cfg(feature = "ndarray")]
+pub use array::ScratchArray2;
+#[cfg(feature = "bumpalo")]
+pub use bump::{with_thread_local_bump, BumpScratch};
+#[cfg(feature = "single-threaded")]
+pub use global::{with_global_workspace, GlobalWorkspace};
+use memory::MemoryUsageFn;
+#[cfg(feature = "metrics")]
+pub use metrics::TypeStats;
+use policy::ApplyPolicyFn;
+#[cfg(feature = "rayon")]
+pub use rayon_pool::clear_workspace_on_all_threads;
+#[cfg(feature = "rand")]
+pub use rng::RngScratch;
+
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::{TryReserveError, VecDeque};
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread::LocalKey;
 
+#[cfg(feature = "warn-on-many-types")]
+const DEFAULT_MANY_TYPES_THRESHOLD: usize = 32;
+
 /// A workspace that contains type-erased objects.
 ///
 /// The workspace is intended to hold intermediate data used as workspace in computations.
@@ -159,12 +232,317 @@ use std::thread::LocalKey;
 /// [`define_thread_local_workspace`] in conjunction with
 /// [`with_thread_local_workspace`] as described in the
 /// [crate-level documentation](`crate`).
-#[derive(Debug, Default)]
+///
+/// ## Thread safety
+///
+/// `Workspace` stores arbitrary `'static` objects, which need not be thread-safe. It is
+/// therefore neither [`Send`] nor [`Sync`], and must stay that way for thread-local
+/// workspaces to be sound. The following examples are checked to fail to compile:
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<davenport::Workspace>();
+/// ```
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<davenport::Workspace>();
+/// ```
 pub struct Workspace {
-    workspaces: Vec<Box<dyn Any>>,
+    workspaces: Vec<Entry>,
+    observer: Option<Box<dyn FnMut(TypeId, bool)>>,
+    alloc_observer: Option<Box<dyn FnMut(AllocEvent)>>,
+    adapters: Vec<Adapter>,
+    memory_usage_fns: Vec<(TypeId, MemoryUsageFn)>,
+    memory_budget: Option<usize>,
+    cloners: Vec<(TypeId, CloneEntryFn, CloneFromFn)>,
+    teardowns: Vec<(TypeId, TeardownFn)>,
+    // Incremented on every access, see `Workspace::entries_idle_for`
+    generation: u64,
+    // When set, inserting new types panics, see `Workspace::freeze`
+    frozen: bool,
+    #[cfg(feature = "metrics")]
+    stats: Vec<(TypeId, TypeStats)>,
+    #[cfg(feature = "metrics")]
+    thrash_detector: metrics::ThrashDetector,
+    #[cfg(feature = "ttl")]
+    clock: Option<Box<dyn Fn() -> std::time::Instant>>,
+    #[cfg(feature = "warn-on-many-types")]
+    many_types_threshold: usize,
+    #[cfg(feature = "warn-on-many-types")]
+    warned_many_types: bool,
+}
+
+/// A type-erased object stored in a [`Workspace`], along with associated metadata.
+struct Entry {
+    value: Box<dyn Any>,
+    #[cfg(feature = "debug-names")]
+    type_name: &'static str,
+    capacity_report: Option<fn(&dyn Any) -> usize>,
+    high_water_mark: usize,
+    // The workspace generation at the time of the last access
+    last_access: u64,
+    // The time of the last access, set when the entry is pushed into a workspace
+    #[cfg(feature = "ttl")]
+    last_access_time: Option<std::time::Instant>,
+    #[cfg(feature = "zeroize")]
+    zeroizer: Option<fn(&mut dyn Any)>,
+    memory_policy: Option<(MemoryPolicy, ApplyPolicyFn)>,
+    resetter: Option<ResetFn>,
+    #[cfg(feature = "debug-backtrace")]
+    creation_backtrace: Option<Box<std::backtrace::Backtrace>>,
+    // For entries of type `Keyed<K, W>`: the type of `W` and an accessor for it
+    keyed_value: Option<(TypeId, KeyedValueFn)>,
+}
+
+type KeyedValueFn = fn(&dyn Any) -> &dyn Any;
+
+type ResetFn = Box<dyn Fn(&mut dyn Any)>;
+
+type CloneEntryFn = fn(&dyn Any) -> Entry;
+
+type CloneFromFn = fn(&mut dyn Any, &dyn Any);
+
+type TeardownFn = fn(&mut dyn Any, &mut Workspace);
+
+fn teardown_any<W: 'static + Teardown>(any: &mut dyn Any, workspace: &mut Workspace) {
+    let w: &mut W = any
+        .downcast_mut()
+        .expect("Internal error: Teardown is only applied to matching type");
+    w.teardown(workspace);
+}
+
+fn clone_entry<W: 'static + Clone>(any: &dyn Any) -> Entry {
+    let w: &W = any
+        .downcast_ref()
+        .expect("Internal error: Cloner is only applied to matching type");
+    Entry::new(w.clone())
+}
+
+fn clone_from_any<W: 'static + Clone>(dst: &mut dyn Any, src: &dyn Any) {
+    let dst: &mut W = dst
+        .downcast_mut()
+        .expect("Internal error: Cloner is only applied to matching type");
+    let src: &W = src
+        .downcast_ref()
+        .expect("Internal error: Cloner is only applied to matching type");
+    dst.clone_from(src);
+}
+
+// Type name of entries whose concrete type was not known statically when they were inserted
+#[cfg(feature = "debug-names")]
+const UNKNOWN_TYPE_NAME: &str = "<unknown>";
+
+impl Entry {
+    fn new<W: 'static>(w: W) -> Self {
+        #[allow(unused_mut)]
+        let mut entry = Self::from_boxed(Box::new(w));
+        #[cfg(feature = "debug-names")]
+        {
+            entry.type_name = std::any::type_name::<W>();
+        }
+        entry
+    }
+
+    fn from_boxed(value: Box<dyn Any>) -> Self {
+        Self {
+            value,
+            #[cfg(feature = "debug-names")]
+            type_name: UNKNOWN_TYPE_NAME,
+            capacity_report: None,
+            high_water_mark: 0,
+            last_access: 0,
+            #[cfg(feature = "ttl")]
+            last_access_time: None,
+            #[cfg(feature = "zeroize")]
+            zeroizer: None,
+            memory_policy: None,
+            resetter: None,
+            #[cfg(feature = "debug-backtrace")]
+            creation_backtrace: None,
+            keyed_value: None,
+        }
+    }
+
+    /// Returns the contained object if it has type `W`, or if it is stored under a key and the
+    /// keyed object has type `W`.
+    fn downcast_ref_unkeyed<W: 'static>(&self) -> Option<&W> {
+        match self.keyed_value {
+            Some((type_id, get)) if type_id == TypeId::of::<W>() => {
+                get(&*self.value).downcast_ref()
+            }
+            _ => self.downcast_ref(),
+        }
+    }
+
+    /// Checks that the recorded type name matches `W`, to catch `TypeId` collisions, e.g.
+    /// across dynamically loaded libraries, before they lead to mixups.
+    #[cfg(all(feature = "debug-names", debug_assertions))]
+    fn assert_type_name<W: 'static>(&self) {
+        if self.type_name == UNKNOWN_TYPE_NAME {
+            return;
+        }
+        assert_eq!(
+            self.type_name,
+            std::any::type_name::<W>(),
+            "Workspace entry with matching TypeId has a different type name. \
+             This indicates a TypeId collision."
+        );
+    }
+
+    /// Drops the entry, zeroizing it first if requested.
+    fn discard(self) {
+        #[cfg(feature = "zeroize")]
+        {
+            let mut entry = self;
+            if let Some(zeroize) = entry.zeroizer {
+                zeroize(&mut *entry.value);
+            }
+        }
+    }
+
+    fn apply_memory_policy(&mut self) {
+        if let Some((policy, apply)) = self.memory_policy {
+            apply(&mut *self.value, policy);
+        }
+    }
+
+    /// Updates metadata that is maintained on every access.
+    fn record_access(&mut self) {
+        self.high_water_mark = self.current_high_water_mark().unwrap_or(0);
+    }
+
+    fn current_high_water_mark(&self) -> Option<usize> {
+        self.capacity_report
+            .map(|report| self.high_water_mark.max(report(&*self.value)))
+    }
+
+    fn value_type_id(&self) -> TypeId {
+        (*self.value).type_id()
+    }
+
+    fn is<W: 'static>(&self) -> bool {
+        self.value.is::<W>()
+    }
+
+    fn downcast_ref<W: 'static>(&self) -> Option<&W> {
+        self.value.downcast_ref()
+    }
+
+    fn downcast_mut<W: 'static>(&mut self) -> Option<&mut W> {
+        self.value.downcast_mut()
+    }
+}
+
+impl fmt::Debug for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "debug-names")]
+        {
+            f.write_str(self.type_name)
+        }
+        #[cfg(not(feature = "debug-names"))]
+        {
+            write!(f, "{:?}", self.value_type_id())
+        }
+    }
+}
+
+/// Wrapper for objects stored under a key type, so that the type of the entry is determined by
+/// both the key and the object type.
+struct Keyed<K, W> {
+    value: W,
+    marker: PhantomData<fn() -> K>,
+}
+
+fn keyed_value<K: 'static, W: 'static>(any: &dyn Any) -> &dyn Any {
+    let keyed: &Keyed<K, W> = any
+        .downcast_ref()
+        .expect("Internal error: Accessor is only applied to matching type");
+    &keyed.value
+}
+
+impl<K, W: Default> Default for Keyed<K, W> {
+    fn default() -> Self {
+        Self {
+            value: W::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// A registered conversion from a concrete workspace type to a trait object type.
+struct Adapter {
+    value_type: TypeId,
+    target_type: TypeId,
+    // Holds a `Box<dyn Fn(&mut dyn Any) -> &mut T>` for the target type `T`
+    cast: Box<dyn Any>,
+}
+
+type CastFn<T> = Box<dyn for<'a> Fn(&'a mut (dyn Any + 'static)) -> &'a mut T>;
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        self.drop_entries();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+fn zeroize_any<W: 'static + zeroize::Zeroize>(any: &mut dyn Any) {
+    if let Some(w) = any.downcast_mut::<W>() {
+        w.zeroize();
+    }
+}
+
+impl fmt::Debug for Workspace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Workspace")
+            .field("workspaces", &self.workspaces)
+            .field("has_observer", &self.observer.is_some())
+            .field("has_alloc_observer", &self.alloc_observer.is_some())
+            .field("num_adapters", &self.adapters.len())
+            .field("memory_budget", &self.memory_budget)
+            .field("num_cloners", &self.cloners.len())
+            .field("num_teardowns", &self.teardowns.len())
+            .field("frozen", &self.frozen)
+            .finish()
+    }
 }
 
 impl Workspace {
+    /// Creates a new, empty workspace.
+    ///
+    /// Unlike [`Default::default`], this can be used in `const` contexts.
+    pub const fn new() -> Self {
+        Self {
+            workspaces: Vec::new(),
+            observer: None,
+            alloc_observer: None,
+            adapters: Vec::new(),
+            memory_usage_fns: Vec::new(),
+            memory_budget: None,
+            cloners: Vec::new(),
+            teardowns: Vec::new(),
+            generation: 0,
+            frozen: false,
+            #[cfg(feature = "metrics")]
+            stats: Vec::new(),
+            #[cfg(feature = "metrics")]
+            thrash_detector: metrics::ThrashDetector::new(),
+            #[cfg(feature = "ttl")]
+            clock: None,
+            #[cfg(feature = "warn-on-many-types")]
+            many_types_threshold: DEFAULT_MANY_TYPES_THRESHOLD,
+            #[cfg(feature = "warn-on-many-types")]
+            warned_many_types: false,
+        }
+    }
+
     /// Attempts to insert the given object into the workspace.
     ///
     /// If the insertion was successful, a reference to the object is returned. Otherwise,
@@ -172,13 +550,75 @@ impl Workspace {
     #[must_use]
     pub fn try_insert<W: 'static>(&mut self, w: W) -> Option<&mut W> {
         if self.find_index_of::<W>().is_none() {
-            self.workspaces.push(Box::from(w));
-            self.workspaces.last_mut().unwrap().downcast_mut()
+            self.push_entry(Entry::new(w)).downcast_mut()
         } else {
             None
         }
     }
 
+    /// Inserts the given object into the workspace unless an object of the same type is
+    /// already present.
+    ///
+    /// Returns a reference to the object stored in the workspace, along with the rejected
+    /// object if insertion did not take place. Unlike [`Workspace::try_insert`], the passed-in
+    /// object is therefore never silently dropped.
+    #[must_use]
+    pub fn try_insert_or<W: 'static>(&mut self, w: W) -> (&mut W, Option<W>) {
+        match self.find_index_of::<W>() {
+            Some(idx) => {
+                let existing = self.workspaces[idx]
+                    .downcast_mut()
+                    .expect("Internal error: Downcasting can by definition not fail");
+                (existing, Some(w))
+            }
+            None => {
+                let inserted = self.push_entry(Entry::new(w)).downcast_mut().unwrap();
+                (inserted, None)
+            }
+        }
+    }
+
+    /// Stores the given object in the workspace, returning the object of the same type that it
+    /// replaces.
+    ///
+    /// If no object of type `W` is present, `new` is inserted and a default-constructed object
+    /// is returned. This expresses double buffering, where a computation alternates between a
+    /// previous and a current buffer, without having to keep a second buffer in the workspace.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// let mut ws = Workspace::default();
+    /// let mut current = vec![1.0, 2.0];
+    /// for _ in 0..3 {
+    ///     let mut next = ws.swap_in(Vec::new());
+    ///     next.clear();
+    ///     next.extend(current.iter().map(|x| 0.5 * x));
+    ///     let previous = std::mem::replace(&mut current, next);
+    ///     ws.swap_in(previous);
+    /// }
+    /// assert_eq!(current, [0.125, 0.25]);
+    /// ```
+    ///
+    /// ## Panics
+    ///
+    /// Panics if no object of type `W` is present and the workspace is
+    /// [frozen](Workspace::freeze).
+    pub fn swap_in<W: 'static + Default>(&mut self, new: W) -> W {
+        match self.find_index_of::<W>() {
+            Some(idx) => {
+                self.notify_dropped(idx);
+                let previous = std::mem::replace(self.get_or_default(), new);
+                self.notify_created(self.workspaces.len() - 1);
+                previous
+            }
+            None => {
+                self.get_or_insert_with(|| new);
+                W::default()
+            }
+        }
+    }
+
     pub fn try_get<W: 'static>(&self) -> Option<&W> {
         self.workspaces
             .iter()
@@ -193,34 +633,458 @@ impl Workspace {
             .find_map(|ws| ws.downcast_mut())
     }
 
+    /// Moves the object of type `W` to the most-recently-used position, if present.
+    ///
+    /// This can be used as a hint that `W` is about to be accessed frequently, so that
+    /// subsequent lookups find it immediately. Returns `true` if an object of type `W` is
+    /// present. If not, `false` is returned and nothing is inserted.
+    pub fn touch<W: 'static>(&mut self) -> bool {
+        match self.find_index_of::<W>() {
+            Some(idx) => {
+                self.workspaces[idx..].rotate_left(1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns a shared reference to the cell holding the object of type `W`, inserting a cell
+    /// with a default-constructed object if it is not present.
+    ///
+    /// The object is stored as a `RefCell<W>`, which is a different type than `W`, so it is
+    /// not shared with e.g. [`Workspace::get_or_default::<W>`](Workspace::get_or_default).
+    /// Since this method takes `&mut self`, use it to insert the cells first, and then obtain
+    /// several cells at once through [`Workspace::try_get_cell`]. This trades static borrow
+    /// checking for runtime checking.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// let mut ws = Workspace::default();
+    /// ws.get_cell::<Vec<u32>>();
+    /// ws.get_cell::<Vec<f64>>();
+    ///
+    /// let ints = ws.try_get_cell::<Vec<u32>>().unwrap();
+    /// let floats = ws.try_get_cell::<Vec<f64>>().unwrap();
+    /// ints.borrow_mut().push(1);
+    /// floats.borrow_mut().push(1.0);
+    /// ```
+    pub fn get_cell<W: 'static + Default>(&mut self) -> &RefCell<W> {
+        self.get_or_default()
+    }
+
+    /// Returns a shared reference to the cell holding the object of type `W`, if present.
+    ///
+    /// See [`Workspace::get_cell`].
+    pub fn try_get_cell<W: 'static>(&self) -> Option<&RefCell<W>> {
+        self.try_get()
+    }
+
+    /// Removes the object of type `W` from the workspace and returns it, or returns a
+    /// default-constructed object if no object of type `W` is present.
+    ///
+    /// The workspace is left without an object of type `W`, so the next access inserts a new
+    /// one. This is useful when the object must be handed to code that takes ownership of it.
+    pub fn take_or_default<W: 'static + Default>(&mut self) -> W {
+        match self.find_index_of::<W>() {
+            Some(idx) => {
+                self.notify_dropped(idx);
+                *self
+                    .workspaces
+                    .remove(idx)
+                    .value
+                    .downcast()
+                    .expect("Internal error: Downcasting can by definition not fail")
+            }
+            None => W::default(),
+        }
+    }
+
+    /// Stores `value` wrapped in [`ManuallyDrop`], so that the workspace never runs its
+    /// destructor.
+    ///
+    /// This is intended for handles to resources owned by other code, e.g. buffers allocated
+    /// by C code across an FFI boundary, which must be cached but not freed by Rust. The
+    /// caller is responsible for releasing the resource, typically after retrieving it with
+    /// [`Workspace::take_manually_dropped`]. If the workspace is cleared or dropped while
+    /// holding the value, the value is leaked rather than dropped.
+    ///
+    /// Returns the previously stored value of type `W`, if any, which is likewise not dropped.
+    /// The value is stored under the type `ManuallyDrop<W>`, and therefore never returned by
+    /// accesses to `W` itself.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// let mut ws = Workspace::default();
+    /// assert!(ws.insert_manually_dropped(vec![1u8, 2]).is_none());
+    /// let buffer = ws.take_manually_dropped::<Vec<u8>>().unwrap();
+    /// // Ownership is back with the caller, who decides how to release the buffer
+    /// let buffer = std::mem::ManuallyDrop::into_inner(buffer);
+    /// assert_eq!(buffer, [1, 2]);
+    /// ```
+    pub fn insert_manually_dropped<W: 'static>(&mut self, value: W) -> Option<ManuallyDrop<W>> {
+        let value = ManuallyDrop::new(value);
+        match self.find_index_of::<ManuallyDrop<W>>() {
+            Some(idx) => {
+                self.notify_dropped(idx);
+                let existing = self.workspaces[idx]
+                    .downcast_mut()
+                    .expect("Internal error: Downcasting can by definition not fail");
+                let previous = std::mem::replace(existing, value);
+                self.notify_created(idx);
+                Some(previous)
+            }
+            None => {
+                let _ = self.try_insert(value);
+                None
+            }
+        }
+    }
+
+    /// Returns the value stored with [`Workspace::insert_manually_dropped`], if present.
+    pub fn get_manually_dropped<W: 'static>(&mut self) -> Option<&mut W> {
+        self.try_get_mut::<ManuallyDrop<W>>().map(|w| &mut **w)
+    }
+
+    /// Removes the value stored with [`Workspace::insert_manually_dropped`] from the workspace,
+    /// and returns it.
+    ///
+    /// The caller takes over responsibility for releasing the value.
+    pub fn take_manually_dropped<W: 'static>(&mut self) -> Option<ManuallyDrop<W>> {
+        let idx = self.find_index_of::<ManuallyDrop<W>>()?;
+        self.notify_dropped(idx);
+        let value = self
+            .workspaces
+            .remove(idx)
+            .value
+            .downcast()
+            .expect("Internal error: Downcasting can by definition not fail");
+        Some(*value)
+    }
+
+    /// Returns the object with the given [`TypeId`] as a type-erased reference, if present.
+    ///
+    /// This supports workflows where the type of the object is only known at runtime, e.g.
+    /// from a registry of [`TypeId`]s. The caller is responsible for downcasting the returned
+    /// reference to the concrete type. Unlike the `get_or_*` methods, this does not change the
+    /// internal order of the workspace.
+    pub fn get_dyn_mut(&mut self, id: TypeId) -> Option<&mut dyn Any> {
+        self.workspaces
+            .iter_mut()
+            .rev()
+            .find(|ws| ws.value_type_id() == id)
+            .map(|ws| &mut *ws.value)
+    }
+
+    /// Inserts a type-erased object, replacing and returning any object of the same type.
+    ///
+    /// This allows hosts of e.g. plugin systems to manage opaque objects whose concrete type
+    /// is not known statically. The object can be retrieved with [`Workspace::get_dyn_mut`],
+    /// or with any of the typed methods if the concrete type is known at the call site.
+    /// Replacing an object only swaps out the object itself, so that e.g. a memory policy,
+    /// reset function or zeroization attached to the stored object carries over to the new one.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `id` is not the [`TypeId`] of the boxed object. Note that the `TypeId` of a
+    /// `Box<dyn Any>` is obtained with `(*value).type_id()`, not `value.type_id()`.
+    pub fn insert_dyn(&mut self, id: TypeId, value: Box<dyn Any>) -> Option<Box<dyn Any>> {
+        assert_eq!(
+            id,
+            (*value).type_id(),
+            "The given TypeId does not match the type of the inserted object"
+        );
+        match self
+            .workspaces
+            .iter()
+            .rposition(|ws| ws.value_type_id() == id)
+        {
+            Some(idx) => {
+                self.notify_dropped(idx);
+                let previous = std::mem::replace(&mut self.workspaces[idx].value, value);
+                self.notify_created(idx);
+                Some(previous)
+            }
+            None => {
+                self.push_entry(Entry::from_boxed(value));
+                None
+            }
+        }
+    }
+
+    /// Returns the types of all objects that have been idle for at least the given number of
+    /// generations.
+    ///
+    /// Every access through [`Workspace::get_or_insert_with`] and the methods built on it
+    /// advances the generation of the workspace by one. An object is idle for `n` generations
+    /// if there have been `n` accesses to other objects since it was last accessed or inserted.
+    /// This provides a measure of staleness that does not depend on a wall clock, and can for
+    /// example be used to evict objects with [`Workspace::extract_if`].
+    ///
+    /// The types are returned in the internal order, i.e. starting with the least recently used
+    /// object, see [`Workspace::peek_lru_type_id`].
+    pub fn entries_idle_for(&self, generations: u64) -> Vec<TypeId> {
+        self.workspaces
+            .iter()
+            .filter(|ws| self.generation - ws.last_access >= generations)
+            .map(Entry::value_type_id)
+            .collect()
+    }
+
+    /// Returns the type of the object at the least recently used end of the internal order.
+    ///
+    /// The workspace keeps its objects in an internal order, in which the front holds the least
+    /// recently used object and the back the most recently used one. This order determines
+    /// lookup performance and which objects are evicted first under a memory budget, see
+    /// [`Workspace::set_memory_budget`].
+    ///
+    /// Accessing an object moves it to the back, shifting the objects behind it one position
+    /// towards the front, so that the order is a true LRU order: after inserting `A`, `B` and
+    /// `C` and then accessing `A`, the order is `B`, `C`, `A`.
+    pub fn peek_lru_type_id(&self) -> Option<TypeId> {
+        self.workspaces.first().map(Entry::value_type_id)
+    }
+
+    /// Returns the type of the most recently used object.
+    ///
+    /// See [`Workspace::peek_lru_type_id`] for a description of the internal order.
+    pub fn peek_mru_type_id(&self) -> Option<TypeId> {
+        self.workspaces.last().map(Entry::value_type_id)
+    }
+
+    /// Rearranges the internal order of the workspace. **Intended for testing only.**
+    ///
+    /// Objects whose types appear in `order` are moved to the front, in the given order,
+    /// followed by all other objects in their previous relative order. Types in `order` that
+    /// are not present are ignored. The last object is considered the most recently used.
+    ///
+    /// The internal order affects lookup performance and eviction under a memory budget, but
+    /// never which object is returned for a given type. This method allows tests to arrange
+    /// the order deterministically in order to verify that code does not depend on it. Note
+    /// that rearranging invalidates all [`WorkspaceHandle`]s.
+    pub fn set_order_for_testing(&mut self, order: &[TypeId]) {
+        let rank = |ws: &Entry| {
+            order
+                .iter()
+                .position(|id| *id == ws.value_type_id())
+                .unwrap_or(order.len())
+        };
+        self.workspaces.sort_by_key(rank);
+    }
+
+    /// Returns the backtrace captured when the object of type `W` was created, if present.
+    ///
+    /// A backtrace is captured whenever an access through [`Workspace::get_or_insert_with`]
+    /// or the methods built on it creates a new object, which helps to find the call site
+    /// responsible for e.g. a large buffer identified with [`Workspace::memory_usage`].
+    /// Reusing an existing object does not capture a backtrace, so the overhead is limited to
+    /// cold misses. Objects inserted in other ways, e.g. with [`Workspace::try_insert`], have
+    /// no backtrace.
+    #[cfg(feature = "debug-backtrace")]
+    pub fn creation_backtrace<W: 'static>(&self) -> Option<&std::backtrace::Backtrace> {
+        self.find_index_of::<W>()
+            .and_then(|idx| self.workspaces[idx].creation_backtrace.as_deref())
+    }
+
+    /// Returns a clone of the object of type `W`, if present.
+    ///
+    /// The stored object and the internal order of the workspace are left untouched.
+    pub fn clone_type<W: 'static + Clone>(&self) -> Option<W> {
+        self.try_get::<W>().cloned()
+    }
+
+    /// Registers `W` for cloning with [`Workspace::clone_registered_into`].
+    pub fn register_clone<W: 'static + Clone>(&mut self) {
+        let type_id = TypeId::of::<W>();
+        if !self.cloners.iter().any(|(id, _, _)| *id == type_id) {
+            self.cloners
+                .push((type_id, clone_entry::<W>, clone_from_any::<W>));
+        }
+    }
+
+    /// Clones all objects of types registered with [`Workspace::register_clone`] into `dst`.
+    ///
+    /// This can be used to transfer warmed-up buffers to another workspace without giving them
+    /// up. Objects already present in `dst` are overwritten with [`Clone::clone_from`], which
+    /// allows them to reuse their allocations, and the registrations are carried over to `dst`.
+    /// Objects of types that have not been registered are skipped.
+    pub fn clone_registered_into(&self, dst: &mut Workspace) {
+        for entry in &self.workspaces {
+            let type_id = entry.value_type_id();
+            let cloner = self.cloners.iter().find(|(id, _, _)| *id == type_id);
+            if let Some(&(_, clone, clone_from)) = cloner {
+                match dst
+                    .workspaces
+                    .iter()
+                    .rposition(|ws| ws.value_type_id() == type_id)
+                {
+                    Some(idx) => {
+                        // Clone into the existing object, to reuse its allocations
+                        dst.notify_dropped(idx);
+                        let existing = &mut dst.workspaces[idx];
+                        #[cfg(feature = "zeroize")]
+                        if let Some(zeroize) = existing.zeroizer {
+                            zeroize(&mut *existing.value);
+                        }
+                        clone_from(&mut *existing.value, &*entry.value);
+                        dst.notify_created(idx);
+                    }
+                    None => {
+                        dst.push_entry(clone(&*entry.value));
+                    }
+                }
+                if !dst.cloners.iter().any(|(id, _, _)| *id == type_id) {
+                    dst.cloners.push((type_id, clone, clone_from));
+                }
+            }
+        }
+    }
+
+    /// Returns the object of type `W`, inserting the object returned by `create` if no object
+    /// of type `W` is present.
+    ///
+    /// ## Panic safety
+    ///
+    /// If `create` panics, the workspace is left exactly as it was before the call: the new
+    /// object is only inserted after it has been successfully created, so no partially
+    /// initialized entry is ever stored.
+    #[inline]
     pub fn get_or_insert_with<W, F>(&mut self, create: F) -> &mut W
     where
         W: 'static,
         F: FnOnce() -> W,
     {
-        let existing_ws_idx = self.find_index_of::<W>();
-        let idx = match existing_ws_idx {
-            Some(idx) => idx,
-            None => {
-                let w = create();
-                let idx = self.workspaces.len();
-                self.workspaces.push(Box::new(w) as Box<dyn Any>);
-                idx
-            }
-        };
+        self.get_or_insert_entry_with(create)
+            .downcast_mut()
+            .expect("Internal error: Downcasting can by definition not fail")
+    }
 
-        // We heuristically assume that the same object is likely to be accessed
-        // many times in sequence. Therefore we make sure that the object is the last entry,
-        // so that on the next lookup, we'll immediately find the correct object
-        let last = self.workspaces.len() - 1;
-        self.workspaces.swap(idx, last);
+    /// Same as [`Workspace::get_or_insert_with`], but returns an error instead of panicking if
+    /// the workspace is [frozen](Workspace::freeze) and holds no object of type `W`.
+    pub fn try_get_or_insert_with<W, F>(
+        &mut self,
+        create: F,
+    ) -> Result<&mut W, FrozenWorkspaceError>
+    where
+        W: 'static,
+        F: FnOnce() -> W,
+    {
+        if self.frozen && self.find_index_of::<W>().is_none() {
+            Err(FrozenWorkspaceError {
+                type_name: std::any::type_name::<W>(),
+            })
+        } else {
+            Ok(self.get_or_insert_with(create))
+        }
+    }
+
+    /// Same as [`Workspace::get_or_default`], but returns an error instead of panicking if the
+    /// workspace is [frozen](Workspace::freeze) and holds no object of type `W`.
+    pub fn try_get_or_default<W>(&mut self) -> Result<&mut W, FrozenWorkspaceError>
+    where
+        W: 'static + Default,
+    {
+        self.try_get_or_insert_with(Default::default)
+    }
+
+    /// Freezes the workspace, so that no objects of new types can be inserted.
+    ///
+    /// Objects already present can still be accessed and modified. Any access that would insert
+    /// an object of a new type panics instead, or returns an error with the `try_` variants
+    /// such as [`Workspace::try_get_or_default`]. This turns "no new workspace types past
+    /// warmup" into a checked contract, catching types that unexpectedly allocate in a hot
+    /// loop. Explicitly inserting an already constructed object, e.g. with
+    /// [`Workspace::try_insert`], is still permitted.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// let mut ws = Workspace::default();
+    /// ws.get_or_default::<Vec<f64>>().reserve(100);
+    /// ws.freeze();
+    /// ws.get_or_default::<Vec<f64>>().push(1.0);
+    /// assert!(ws.try_get_or_default::<Vec<u32>>().is_err());
+    /// ```
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    /// Lifts a previous [`Workspace::freeze`].
+    pub fn unfreeze(&mut self) {
+        self.frozen = false;
+    }
 
-        let entry = &mut self.workspaces[last];
+    /// Returns `true` if the workspace is frozen, see [`Workspace::freeze`].
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Same as [`Workspace::get_or_insert_with`], but additionally stores `reset` with the
+    /// object, which can later be applied with [`Workspace::reset_type`].
+    ///
+    /// This allows resetting objects of types that do not implement [`Default`], or whose
+    /// reset should retain resources. If the object is already present, its previously stored
+    /// reset function, if any, is replaced.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// struct Histogram {
+    ///     bins: Vec<u32>,
+    /// }
+    ///
+    /// let mut ws = Workspace::default();
+    /// let histogram = ws.get_or_insert_with_resetter(
+    ///     || Histogram { bins: vec![0; 16] },
+    ///     |h| h.bins.fill(0),
+    /// );
+    /// histogram.bins[3] += 1;
+    /// assert!(ws.reset_type::<Histogram>());
+    /// assert_eq!(ws.try_get::<Histogram>().unwrap().bins, [0; 16]);
+    /// ```
+    #[inline]
+    pub fn get_or_insert_with_resetter<W, F>(
+        &mut self,
+        create: F,
+        reset: impl 'static + Fn(&mut W),
+    ) -> &mut W
+    where
+        W: 'static,
+        F: FnOnce() -> W,
+    {
+        let entry = self.get_or_insert_entry_with(create);
+        entry.resetter = Some(Box::new(move |any| {
+            let w = any
+                .downcast_mut()
+                .expect("Internal error: Resetter is only applied to matching type");
+            reset(w)
+        }));
         entry
             .downcast_mut()
             .expect("Internal error: Downcasting can by definition not fail")
     }
 
+    /// Resets the object of type `W` with the reset function stored by
+    /// [`Workspace::get_or_insert_with_resetter`].
+    ///
+    /// Returns `false` if no object of type `W` is present, or if no reset function is stored
+    /// for it, in which case the object is left as is.
+    pub fn reset_type<W: 'static>(&mut self) -> bool {
+        let entry = match self.find_index_of::<W>() {
+            Some(idx) => &mut self.workspaces[idx],
+            None => return false,
+        };
+        match &entry.resetter {
+            Some(reset) => {
+                reset(&mut *entry.value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[inline]
     pub fn get_or_default<W>(&mut self) -> &mut W
     where
         W: 'static + Default,
@@ -228,48 +1092,2348 @@ impl Workspace {
         self.get_or_insert_with(Default::default)
     }
 
-    fn find_index_of<W: 'static>(&self) -> Option<usize> {
-        // Note: We treat the Vec as a stack, so we search from the end of the vector.
-        self.workspaces.iter().rposition(|ws| ws.is::<W>())
+    /// Returns the objects of types `A` and `B` at the same time, inserting default-constructed
+    /// objects if they are not present.
+    ///
+    /// This avoids having to juggle two separate borrows of the workspace when a computation
+    /// needs several buffers at once.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// let mut ws = Workspace::default();
+    /// let (indices, values) = ws.get_pair_mut::<Vec<usize>, Vec<f64>>();
+    /// indices.push(0);
+    /// values.push(1.0);
+    /// ```
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `A` and `B` are the same type.
+    pub fn get_pair_mut<A, B>(&mut self) -> (&mut A, &mut B)
+    where
+        A: 'static + Default,
+        B: 'static + Default,
+    {
+        assert_ne!(
+            TypeId::of::<A>(),
+            TypeId::of::<B>(),
+            "Can not mutably borrow the same type twice"
+        );
+        // Enforce the memory budget only once both objects are present, so that accessing B
+        // can not evict A
+        self.access_entry_at(self.find_index_of::<A>(), A::default);
+        self.access_entry_at(self.find_index_of::<B>(), B::default);
+        self.enforce_memory_budget_sparing(&[TypeId::of::<A>(), TypeId::of::<B>()]);
+        let a_idx = self.find_index_of::<A>().unwrap();
+        let b_idx = self.find_index_of::<B>().unwrap();
+        let (a, b) = if a_idx < b_idx {
+            let (head, tail) = self.workspaces.split_at_mut(b_idx);
+            (&mut head[a_idx], &mut tail[0])
+        } else {
+            let (head, tail) = self.workspaces.split_at_mut(a_idx);
+            (&mut tail[0], &mut head[b_idx])
+        };
+        let a = a
+            .downcast_mut()
+            .expect("Internal error: Downcasting can by definition not fail");
+        let b = b
+            .downcast_mut()
+            .expect("Internal error: Downcasting can by definition not fail");
+        (a, b)
     }
-}
+
+    /// Same as [`Workspace::get_pair_mut`], but returns an error instead of panicking if `A` and
+    /// `B` are the same type.
+    pub fn try_get_pair_mut<A, B>(&mut self) -> Result<(&mut A, &mut B), WorkspaceError>
+    where
+        A: 'static + Default,
+        B: 'static + Default,
+    {
+        if TypeId::of::<A>() == TypeId::of::<B>() {
+            Err(WorkspaceError::DuplicateType {
+                type_name: std::any::type_name::<A>(),
+            })
+        } else {
+            Ok(self.get_pair_mut())
+        }
+    }
+
+    /// Returns the object of type `W`, inserting a default-constructed object if not present,
+    /// along with access to all other objects in the workspace.
+    ///
+    /// The returned [`WorkspaceRest`] provides mutable access to objects of any type other
+    /// than `W`, while `W` itself is borrowed. Since the rest borrows the workspace, it can
+    /// only access objects that are already present, and can not insert new ones.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// let mut ws = Workspace::default();
+    /// ws.get_or_default::<Vec<f64>>().push(2.0);
+    /// let (indices, mut rest) = ws.split_off::<Vec<usize>>();
+    /// let values = rest.get_mut::<Vec<f64>>().unwrap();
+    /// indices.push(0);
+    /// values[indices[0]] *= 2.0;
+    /// assert!(rest.get_mut::<Vec<usize>>().is_none());
+    /// ```
+    pub fn split_off<W: 'static + Default>(&mut self) -> (&mut W, WorkspaceRest<'_>) {
+        // Leaves the object of type `W` at the back of the workspace
+        let _ = self.get_or_default::<W>();
+        let (last, entries) = self
+            .workspaces
+            .split_last_mut()
+            .expect("Internal error: Workspace can not be empty after insertion");
+        let w = last
+            .downcast_mut()
+            .expect("Internal error: Downcasting can by definition not fail");
+        (w, WorkspaceRest { entries })
+    }
+
+    /// Returns a shared reference to the object of type `W`, computing it with the provided
+    /// closure if it is not present.
+    ///
+    /// This serves values that are computed once and then read many times, such as cached
+    /// derived data. The shared reference signals that the value is not meant to be modified
+    /// in place. Use [`Workspace::invalidate`] to force recomputation on the next access.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// struct Primes(Vec<u32>);
+    ///
+    /// let mut ws = Workspace::default();
+    /// let primes = ws.get_or_compute(|| Primes(vec![2, 3, 5, 7]));
+    /// assert_eq!(primes.0.len(), 4);
+    /// ```
+    pub fn get_or_compute<W: 'static>(&mut self, compute: impl FnOnce() -> W) -> &W {
+        self.get_or_insert_with(compute)
+    }
+
+    /// Returns the object of type `W`, constructing it from the given context with
+    /// [`BuildFromContext::build`] if it is not present.
+    ///
+    /// This is a structured alternative to [`Workspace::get_or_insert_with`] for types that can
+    /// not implement [`Default`], but can be constructed from some context that is threaded
+    /// through the code base. The context is only used on a miss.
+    pub fn get_or_build<W, Ctx>(&mut self, ctx: &Ctx) -> &mut W
+    where
+        W: 'static + BuildFromContext<Ctx>,
+        Ctx: ?Sized,
+    {
+        self.get_or_insert_with(|| W::build(ctx))
+    }
+
+    /// Removes the object of type `W`, if present, so that it is recomputed or
+    /// default-constructed on the next access.
+    ///
+    /// Returns `true` if an object was removed.
+    pub fn invalidate<W: 'static>(&mut self) -> bool {
+        match self.find_index_of::<W>() {
+            Some(idx) => {
+                self.discard_at(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the object of type `W` associated with the key type `K`, inserting a
+    /// default-constructed object if it is not present.
+    ///
+    /// Keys are typically zero-sized marker types, which allow unrelated code to store objects
+    /// of the same type in a shared workspace without colliding. The slot for `W` under key
+    /// `K` is distinct from the slot for `W` accessed without a key, e.g. through
+    /// [`Workspace::get_or_default`], and from the slots for `W` under other keys.
+    ///
+    /// In other words, unkeyed access lives in its own namespace. There is no key that refers
+    /// to the unkeyed slot, and in particular `get_keyed::<(), W>()` is a keyed slot like any
+    /// other. Objects inserted with or without a key are only ever shared across the two
+    /// kinds of access through [`Workspace::iter_of_type`], which visits all of them.
+    ///
+    /// Since a forgotten key silently creates a fresh unkeyed object, the `strict-tags` feature
+    /// makes debug builds panic when an unkeyed object is about to be created for a type that
+    /// is only stored under keys.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// struct ModuleA;
+    /// struct ModuleB;
+    ///
+    /// let mut ws = Workspace::default();
+    /// ws.get_keyed::<ModuleA, Vec<u8>>().push(1);
+    /// assert!(ws.get_keyed::<ModuleB, Vec<u8>>().is_empty());
+    /// assert!(ws.try_get::<Vec<u8>>().is_none());
+    /// ```
+    pub fn get_keyed<K, W>(&mut self) -> &mut W
+    where
+        K: 'static,
+        W: 'static + Default,
+    {
+        let entry = self.get_or_insert_entry_with(Keyed::<K, W>::default);
+        entry.keyed_value = Some((TypeId::of::<W>(), keyed_value::<K, W>));
+        debug_assert_ne!(
+            entry.value_type_id(),
+            TypeId::of::<W>(),
+            "Internal error: Keyed objects must never share the slot of unkeyed objects"
+        );
+        let keyed: &mut Keyed<K, W> = entry
+            .downcast_mut()
+            .expect("Internal error: Downcasting can by definition not fail");
+        &mut keyed.value
+    }
+
+    /// Returns the object of type `W` associated with the key type `K`, if present.
+    ///
+    /// See [`Workspace::get_keyed`].
+    pub fn try_get_keyed<K: 'static, W: 'static>(&self) -> Option<&W> {
+        self.try_get::<Keyed<K, W>>().map(|keyed| &keyed.value)
+    }
+
+    /// Returns an iterator over all objects of type `W`, regardless of key.
+    ///
+    /// This includes the object accessed without a key, e.g. through
+    /// [`Workspace::get_or_default`], as well as all objects of type `W` stored under a key
+    /// with [`Workspace::get_keyed`]. The order of iteration is unspecified.
+    pub fn iter_of_type<W: 'static>(&self) -> impl Iterator<Item = &W> + '_ {
+        self.workspaces
+            .iter()
+            .filter_map(Entry::downcast_ref_unkeyed)
+    }
+
+    /// Returns a slice of length `len` backed by a reused `Vec<T>`.
+    ///
+    /// This captures the common idiom of resizing a buffer before handing it to a function that
+    /// populates a slice. Internally, the `Vec<T>` stored in the workspace is resized with
+    /// [`Vec::resize`], so elements from previous use are preserved up to `len`, and any new
+    /// elements are initialized with `T::default()`. The capacity of the vector is reused.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// let mut ws = Workspace::default();
+    /// let buffer: &mut [u32] = ws.get_slice_or_default(3);
+    /// buffer.copy_from_slice(&[1, 2, 3]);
+    /// assert_eq!(ws.get_or_default::<Vec<u32>>(), &[1, 2, 3]);
+    /// ```
+    pub fn get_slice_or_default<T>(&mut self, len: usize) -> &mut [T]
+    where
+        T: 'static + Default + Clone,
+    {
+        let buffer: &mut Vec<T> = self.get_or_default();
+        buffer.resize(len, T::default());
+        buffer
+    }
+
+    /// Same as [`Workspace::get_slice_or_default`], but grows the `Vec<T>` with
+    /// [`Vec::reserve_exact`] instead of its amortized growth strategy.
+    ///
+    /// The capacity of the vector is then the largest `len` requested so far, rather than
+    /// depending on the sequence of previous lengths and the growth strategy of [`Vec`]. This
+    /// makes the retained memory predictable, e.g. for tests that make assertions about it.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// let mut ws = Workspace::default();
+    /// ws.get_exact_slice_or_default::<u32>(5);
+    /// ws.get_exact_slice_or_default::<u32>(7);
+    /// assert_eq!(ws.get_or_default::<Vec<u32>>().capacity(), 7);
+    /// ```
+    pub fn get_exact_slice_or_default<T>(&mut self, len: usize) -> &mut [T]
+    where
+        T: 'static + Default + Clone,
+    {
+        let buffer: &mut Vec<T> = self.get_or_default();
+        if len > buffer.len() {
+            buffer.reserve_exact(len - buffer.len());
+        }
+        buffer.resize(len, T::default());
+        buffer
+    }
+
+    /// Returns `len` uninitialized elements backed by the capacity of a reused `Vec<T>`.
+    ///
+    /// This is an alternative to [`Workspace::get_slice_or_default`] for buffers that are
+    /// completely overwritten after preparation, e.g. by a `populate_buffer`-style function,
+    /// which avoids initializing the elements with `T::default()` first. The vector is
+    /// cleared, dropping any previous contents, and its capacity is grown to at least `len`
+    /// and reused across calls. Once all elements have been written,
+    /// [`Workspace::assume_init_slice`] provides them as a regular slice.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// fn populate_buffer(buffer: &mut [std::mem::MaybeUninit<u32>]) {
+    ///     for (i, x) in buffer.iter_mut().enumerate() {
+    ///         x.write(i as u32);
+    ///     }
+    /// }
+    ///
+    /// let mut ws = Workspace::default();
+    /// populate_buffer(ws.prepare_uninit::<u32>(4));
+    /// // SAFETY: All 4 elements were written by `populate_buffer`
+    /// let buffer = unsafe { ws.assume_init_slice::<u32>(4) };
+    /// assert_eq!(buffer, [0, 1, 2, 3]);
+    /// ```
+    pub fn prepare_uninit<T: 'static>(&mut self, len: usize) -> &mut [MaybeUninit<T>] {
+        let buffer: &mut Vec<T> = self.get_or_default();
+        buffer.clear();
+        buffer.reserve(len);
+        &mut buffer.spare_capacity_mut()[..len]
+    }
+
+    /// Returns the first `len` elements prepared by [`Workspace::prepare_uninit`], assuming
+    /// that they have been initialized.
+    ///
+    /// The elements then belong to the `Vec<T>` stored in the workspace, and are dropped when
+    /// it is next prepared or cleared.
+    ///
+    /// ## Safety
+    ///
+    /// Since the last call to `prepare_uninit::<T>`, the first `len` elements it returned must
+    /// all have been initialized, and the `Vec<T>` stored in the workspace must not have been
+    /// accessed in any other way.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `len` exceeds the capacity of the stored `Vec<T>`.
+    pub unsafe fn assume_init_slice<T: 'static>(&mut self, len: usize) -> &mut [T] {
+        let buffer: &mut Vec<T> = self.get_or_default();
+        assert!(
+            len <= buffer.capacity(),
+            "Can not assume more elements to be initialized than were prepared."
+        );
+        // SAFETY: The elements are initialized by the contract of this function, and the
+        // preceding `prepare_uninit` left the vector empty, so no elements are duplicated
+        unsafe { buffer.set_len(len) };
+        buffer
+    }
+
+    /// Same as [`Workspace::get_slice_or_default`], but returns an error instead of aborting if
+    /// the required memory can not be allocated.
+    ///
+    /// This is useful when `len` is derived from untrusted input. On error, the buffer is left
+    /// unchanged.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// let mut ws = Workspace::default();
+    /// assert_eq!(ws.try_get_slice_or_default::<u32>(3).unwrap(), &[0, 0, 0]);
+    /// assert!(ws.try_get_slice_or_default::<u32>(usize::MAX).is_err());
+    /// ```
+    pub fn try_get_slice_or_default<T>(&mut self, len: usize) -> Result<&mut [T], TryReserveError>
+    where
+        T: 'static + Default + Clone,
+    {
+        let buffer: &mut Vec<T> = self.get_or_default();
+        buffer.try_reserve(len.saturating_sub(buffer.len()))?;
+        buffer.resize(len, T::default());
+        Ok(buffer)
+    }
+
+    /// Returns a slice of length `len` backed by a reused `Box<[T]>`.
+    ///
+    /// Unlike [`Workspace::get_slice_or_default`], the backing storage never has more capacity
+    /// than it needs. The stored `Box<[T]>` is reused as-is whenever its length is exactly
+    /// `len`, in which case elements from previous use are preserved. If the length differs,
+    /// the box is reallocated with all elements initialized to `T::default()`, so alternating
+    /// between different lengths reallocates on every change.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// let mut ws = Workspace::default();
+    /// ws.get_boxed_slice_or_default::<u32>(3).copy_from_slice(&[1, 2, 3]);
+    /// assert_eq!(ws.get_boxed_slice_or_default::<u32>(3), &[1, 2, 3]);
+    /// assert_eq!(ws.get_boxed_slice_or_default::<u32>(2), &[0, 0]);
+    /// ```
+    pub fn get_boxed_slice_or_default<T>(&mut self, len: usize) -> &mut [T]
+    where
+        T: 'static + Default + Clone,
+    {
+        let buffer: &mut Box<[T]> = self.get_or_default();
+        if buffer.len() != len {
+            *buffer = vec![T::default(); len].into_boxed_slice();
+        }
+        buffer
+    }
+
+    /// Same as [`Workspace::get_boxed_slice_or_default`], but returns an error instead of
+    /// aborting if the required memory can not be allocated.
+    ///
+    /// On error, the stored box is left unchanged.
+    pub fn try_get_boxed_slice_or_default<T>(
+        &mut self,
+        len: usize,
+    ) -> Result<&mut [T], TryReserveError>
+    where
+        T: 'static + Default + Clone,
+    {
+        let buffer: &mut Box<[T]> = self.get_or_default();
+        if buffer.len() != len {
+            let mut new_buffer = Vec::new();
+            new_buffer.try_reserve_exact(len)?;
+            new_buffer.resize(len, T::default());
+            *buffer = new_buffer.into_boxed_slice();
+        }
+        Ok(buffer)
+    }
+
+    /// Returns a fixed-size array of type `[T; N]`, inserting one with all elements set to
+    /// `T::default()` if not present.
+    ///
+    /// The array is stored on the heap inside the workspace, which keeps large scratch arrays
+    /// off the stack of the calling code. It is not reset on reuse, so elements from previous
+    /// use are preserved. Note that the standard library only implements [`Default`] for
+    /// arrays of up to 32 elements, which is why [`Workspace::get_or_default`] can not be used
+    /// for larger arrays, whereas this method works for any `N`. The array is stored under the
+    /// type `[T; N]`, so it is shared with accesses to that type through other methods.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    ///
+    /// let mut ws = Workspace::default();
+    /// let accumulator = ws.get_array_or_default::<f64, 64>();
+    /// accumulator[0] += 1.0;
+    /// assert_eq!(ws.get_array_or_default::<f64, 64>()[0], 1.0);
+    /// ```
+    pub fn get_array_or_default<T, const N: usize>(&mut self) -> &mut [T; N]
+    where
+        T: 'static + Default + Copy,
+    {
+        self.get_or_insert_with(|| [T::default(); N])
+    }
+
+    /// Same as [`Workspace::get_or_default`], but additionally tracks the capacity
+    /// high-water mark of the object.
+    ///
+    /// Once an object has been accessed through this method, its capacity as reported by
+    /// [`CapacityReport`] is recorded on every subsequent access through any of the
+    /// `get_or_*` methods. The peak capacity can be queried with
+    /// [`Workspace::high_water_mark`].
+    pub fn get_or_default_tracked<W>(&mut self) -> &mut W
+    where
+        W: 'static + Default + CapacityReport,
+    {
+        let entry = self.get_or_insert_entry_with(W::default);
+        if entry.capacity_report.is_none() {
+            entry.capacity_report = Some(report_capacity::<W>);
+            entry.record_access();
+        }
+        entry
+            .downcast_mut()
+            .expect("Internal error: Downcasting can by definition not fail")
+    }
+
+    /// Same as [`Workspace::get_or_default`], but additionally makes sure that the object can
+    /// hold at least `hint` elements, see [`WorkspaceReserve`].
+    ///
+    /// The hint is applied on every call, not only when the object is created.
+    pub fn get_or_default_reserving<W>(&mut self, hint: usize) -> &mut W
+    where
+        W: 'static + Default + WorkspaceReserve,
+    {
+        let w = self.get_or_default::<W>();
+        w.reserve_for(hint);
+        w
+    }
+
+    /// Prepares objects of several types at once, e.g. to allocate all buffers needed by a
+    /// computation up front at startup.
+    ///
+    /// The specs are applied in order, see [`PrewarmSpec`].
+    pub fn prewarm(&mut self, specs: impl IntoIterator<Item = PrewarmSpec>) {
+        for spec in specs {
+            (spec.apply)(self);
+        }
+    }
+
+    /// Returns the largest capacity that the object of type `W` has been observed to have.
+    ///
+    /// Returns `None` if there is no object of type `W`, or if it has not been accessed through
+    /// [`Workspace::get_or_default_tracked`]. The current capacity of the object is always taken
+    /// into account, even if it grew after the last access.
+    pub fn high_water_mark<W: 'static>(&self) -> Option<usize> {
+        self.find_index_of::<W>()
+            .and_then(|idx| self.workspaces[idx].current_high_water_mark())
+    }
+
+    /// Returns the current capacities of all objects whose capacity is tracked.
+    ///
+    /// Only objects that have been accessed through [`Workspace::get_or_default_tracked`] are
+    /// included. The snapshot can be used to reconstruct a workspace with preallocated objects
+    /// with [`WorkspaceBuilder`], for example to warm-start a worker.
+    ///
+    /// Note that [`TypeId`]s are only guaranteed to be consistent within the same build of a
+    /// program, so a persisted snapshot must only be restored by the same binary.
+    pub fn capacities_snapshot(&self) -> Vec<(TypeId, usize)> {
+        self.workspaces
+            .iter()
+            .filter_map(|ws| {
+                let report = ws.capacity_report?;
+                Some((ws.value_type_id(), report(&*ws.value)))
+            })
+            .collect()
+    }
+
+    /// Reserves capacity for at least `additional` more distinct types.
+    ///
+    /// Lookups scan the stored objects linearly, starting with the most recently used one,
+    /// so building up a workspace with `n` distinct types costs `O(n^2)` type comparisons in
+    /// total. Reserving up front avoids additionally reallocating the internal storage while
+    /// doing so.
+    pub fn reserve(&mut self, additional: usize) {
+        self.workspaces.reserve(additional);
+    }
+
+    /// Removes all objects from the workspace.
+    ///
+    /// Objects are dropped one at a time in the internal order from back to front, i.e.
+    /// starting with the most recently used object, see [`Workspace::peek_lru_type_id`]. The
+    /// same order applies when the workspace itself is dropped, including when a thread-local
+    /// workspace is destroyed at thread exit.
+    ///
+    /// With the `zeroize` feature, objects accessed through
+    /// [`Workspace::get_or_default_zeroized`] are zeroized before they are dropped.
+    ///
+    /// ## Accessing the workspace during teardown
+    ///
+    /// A [`Drop`] implementation of an object stored in a thread-local workspace must not
+    /// access the same thread-local workspace, e.g. through [`with_thread_local_workspace`].
+    /// The workspace is borrowed while it is cleared, so such access panics, and after thread
+    /// exit the thread-local variable is no longer accessible at all. Objects that need other
+    /// workspace objects during teardown should instead implement [`Teardown`], and be
+    /// registered with [`Workspace::register_teardown`].
+    pub fn clear(&mut self) {
+        self.drop_entries();
+    }
+
+    /// Drops all entries from back to front, as documented for [`Workspace::clear`].
+    fn drop_entries(&mut self) {
+        while let Some(last) = self.workspaces.len().checked_sub(1) {
+            self.notify_dropped(last);
+            let mut entry = self.workspaces.pop().unwrap();
+            let type_id = entry.value_type_id();
+            let teardown = self.teardowns.iter().find(|(id, _)| *id == type_id);
+            if let Some(&(_, teardown)) = teardown {
+                // The entry has been removed, so the rest of the workspace can be lent out
+                teardown(&mut *entry.value, self);
+            }
+            entry.discard();
+        }
+    }
+
+    /// Registers `W` for teardown with access to the workspace, see [`Teardown`].
+    pub fn register_teardown<W: 'static + Teardown>(&mut self) {
+        let type_id = TypeId::of::<W>();
+        if !self.teardowns.iter().any(|(id, _)| *id == type_id) {
+            self.teardowns.push((type_id, teardown_any::<W>));
+        }
+    }
+
+    /// Same as [`Workspace::get_or_default`], but zeroizes an existing object before
+    /// returning it.
+    ///
+    /// This is intended for workspaces holding sensitive data, such as intermediate values in
+    /// cryptographic computations. Objects accessed through this method are furthermore
+    /// zeroized when the workspace is cleared or dropped. Note that values moved out of the
+    /// workspace, for example with [`Workspace::extract_if`], are not zeroized.
+    #[cfg(feature = "zeroize")]
+    pub fn get_or_default_zeroized<W>(&mut self) -> &mut W
+    where
+        W: 'static + Default + zeroize::Zeroize,
+    {
+        let existing = self.find_index_of::<W>().is_some();
+        let entry = self.get_or_insert_entry_with(W::default);
+        entry.zeroizer = Some(zeroize_any::<W>);
+        let w: &mut W = entry
+            .downcast_mut()
+            .expect("Internal error: Downcasting can by definition not fail");
+        if existing {
+            w.zeroize();
+        }
+        w
+    }
+
+    /// Returns a handle that provides O(1) access to the object of type `W`.
+    ///
+    /// If no object of type `W` is present, a default-constructed object is inserted.
+    /// See [`WorkspaceHandle`] for the rules governing when a handle is invalidated.
+    pub fn handle<W: 'static + Default>(&mut self) -> WorkspaceHandle<W> {
+        let _ = self.get_or_default::<W>();
+        WorkspaceHandle {
+            index: self.workspaces.len() - 1,
+            marker: PhantomData,
+        }
+    }
+
+    /// Removes all objects whose [`TypeId`] satisfies the given predicate and returns them.
+    ///
+    /// The relative order of the remaining objects is preserved. The returned objects are
+    /// type-erased, and may be recovered with [`Box::downcast`].
+    pub fn extract_if(&mut self, mut pred: impl FnMut(TypeId) -> bool) -> Vec<Box<dyn Any>> {
+        let extract: Vec<bool> = self
+            .workspaces
+            .iter()
+            .map(|ws| pred(ws.value_type_id()))
+            .collect();
+        for idx in (0..extract.len()).filter(|&idx| extract[idx]) {
+            self.notify_dropped(idx);
+        }
+        let mut extract = extract.into_iter();
+        let (extracted, kept) = std::mem::take(&mut self.workspaces)
+            .into_iter()
+            .partition(|_: &Entry| extract.next().unwrap());
+        self.workspaces = kept;
+        extracted.into_iter().map(|ws| ws.value).collect()
+    }
+
+    /// Registers a conversion from objects of type `W` to the (typically unsized) type `T`.
+    ///
+    /// Since [`Any`] can not be upcast to arbitrary traits, objects stored in the workspace can
+    /// only be visited through a trait with [`Workspace::for_each_as`] if a conversion has been
+    /// registered for their type. Typically `T` is a trait object and the conversion is a simple
+    /// coercion, e.g. `ws.register_as::<MyVec, dyn MyBuffer>(|v| v)`.
+    ///
+    /// Registering a conversion for the same pair of types again replaces the previous one.
+    /// Registrations are independent of whether an object of type `W` is currently stored.
+    pub fn register_as<W: 'static, T: ?Sized + 'static>(&mut self, cast: fn(&mut W) -> &mut T) {
+        let value_type = TypeId::of::<W>();
+        let target_type = TypeId::of::<T>();
+        let cast: CastFn<T> = Box::new(move |any| {
+            cast(
+                any.downcast_mut()
+                    .expect("Internal error: Adapter is only applied to matching type"),
+            )
+        });
+        self.adapters
+            .retain(|a| a.value_type != value_type || a.target_type != target_type);
+        self.adapters.push(Adapter {
+            value_type,
+            target_type,
+            cast: Box::new(cast),
+        });
+    }
+
+    /// Calls the provided closure on every stored object for which a conversion to `T` has been
+    /// registered with [`Workspace::register_as`].
+    ///
+    /// Objects without a registered conversion are skipped.
+    pub fn for_each_as<T: ?Sized + 'static>(&mut self, mut f: impl FnMut(&mut T)) {
+        let target_type = TypeId::of::<T>();
+        for ws in &mut self.workspaces {
+            let value_type = ws.value_type_id();
+            let adapter = self
+                .adapters
+                .iter()
+                .find(|a| a.value_type == value_type && a.target_type == target_type);
+            if let Some(adapter) = adapter {
+                let cast: &CastFn<T> = adapter
+                    .cast
+                    .downcast_ref()
+                    .expect("Internal error: Adapter type is determined by target type");
+                f(cast(&mut *ws.value));
+            }
+        }
+    }
+
+    /// Applies the operation to every stored object for which a conversion to
+    /// [`WorkspaceOp::Target`] has been registered with [`Workspace::register_as`].
+    ///
+    /// This is a structured form of [`Workspace::for_each_as`] for maintenance sweeps, such as
+    /// flushing or resetting all buffers. Operations with the same target type share the same
+    /// registrations, so that a type only needs to be registered once to take part in all
+    /// sweeps over that target.
+    pub fn for_each_registered<Op: WorkspaceOp>(&mut self, op: &mut Op) {
+        self.for_each_as::<Op::Target>(|target| op.apply(target));
+    }
+
+    /// Sets an observer that is invoked on every access through
+    /// [`Workspace::get_or_insert_with`] and the methods built on it.
+    ///
+    /// The observer receives the [`TypeId`] of the accessed type and whether the access was a
+    /// hit, i.e. whether an object of the type was already present. Any previously set observer
+    /// is replaced. When no observer is set, the only overhead is a check of an `Option`.
+    pub fn set_observer(&mut self, observer: Box<dyn FnMut(TypeId, bool)>) {
+        self.observer = Some(observer);
+    }
+
+    /// Sets the number of distinct types above which a warning is logged.
+    ///
+    /// When a new type is inserted and the workspace thereby holds more than `threshold`
+    /// distinct types, a warning listing the stored types is emitted through the `log` crate.
+    /// Type names are only available with the `debug-names` feature. The warning is emitted at
+    /// most once per workspace. The default threshold is 32.
+    #[cfg(feature = "warn-on-many-types")]
+    pub fn set_many_types_threshold(&mut self, threshold: usize) {
+        self.many_types_threshold = threshold;
+    }
+
+    #[cfg(feature = "warn-on-many-types")]
+    fn check_many_types(&mut self) {
+        if !self.warned_many_types && self.workspaces.len() > self.many_types_threshold {
+            self.warned_many_types = true;
+            log::warn!(
+                "Workspace holds {} distinct types, exceeding the threshold of {}. \
+                 This may indicate unintended monomorphization of workspace types. \
+                 Stored types: {:?}",
+                self.workspaces.len(),
+                self.many_types_threshold,
+                self.workspaces
+            );
+        }
+    }
+
+    #[inline]
+    fn get_or_insert_entry_with<W, F>(&mut self, create: F) -> &mut Entry
+    where
+        W: 'static,
+        F: FnOnce() -> W,
+    {
+        let existing_ws_idx = self.find_index_of::<W>();
+        self.get_or_insert_entry_at(existing_ws_idx, create)
+    }
+
+    /// Same as `get_or_insert_entry_with`, but with the index of the object of type `W`
+    /// already looked up with `find_index_of`, to avoid searching twice.
+    #[inline]
+    fn get_or_insert_entry_at<W, F>(
+        &mut self,
+        existing_ws_idx: Option<usize>,
+        create: F,
+    ) -> &mut Entry
+    where
+        W: 'static,
+        F: FnOnce() -> W,
+    {
+        self.access_entry_at(existing_ws_idx, create);
+        self.enforce_memory_budget();
+        self.workspaces.last_mut().unwrap()
+    }
+
+    /// Same as `get_or_insert_entry_at`, but does not enforce the memory budget, so that
+    /// callers accessing several objects at once can enforce it once all of them are present.
+    /// The accessed object is left as the last entry.
+    #[inline]
+    fn access_entry_at<W, F>(&mut self, existing_ws_idx: Option<usize>, create: F)
+    where
+        W: 'static,
+        F: FnOnce() -> W,
+    {
+        if let Some(observer) = &mut self.observer {
+            observer(TypeId::of::<W>(), existing_ws_idx.is_some());
+        }
+        let idx = match existing_ws_idx {
+            Some(idx) => {
+                #[cfg(all(feature = "debug-names", debug_assertions))]
+                self.workspaces[idx].assert_type_name::<W>();
+                idx
+            }
+            None => self.insert_new_entry(create),
+        };
+
+        // We heuristically assume that the same object is likely to be accessed
+        // many times in sequence. Therefore we make sure that the object is the last entry,
+        // so that on the next lookup, we'll immediately find the correct object. Rotating
+        // rather than swapping keeps the remaining objects in order of recency
+        self.workspaces[idx..].rotate_left(1);
+        #[cfg(feature = "metrics")]
+        self.record_access_stats(TypeId::of::<W>(), existing_ws_idx.is_some());
+
+        self.generation += 1;
+        #[cfg(feature = "ttl")]
+        let now = self.now();
+        let entry = self.workspaces.last_mut().unwrap();
+        entry.record_access();
+        entry.last_access = self.generation;
+        #[cfg(feature = "ttl")]
+        {
+            entry.last_access_time = Some(now);
+        }
+    }
+
+    /// Creates and appends an object of type `W`, which must not be present, and returns its
+    /// index.
+    ///
+    /// This is kept out of line, since objects are typically created once and then accessed
+    /// many times, so that the hit path of `get_or_insert_entry_with` stays small enough to be
+    /// inlined into tight loops.
+    #[cold]
+    #[inline(never)]
+    fn insert_new_entry<W: 'static>(&mut self, create: impl FnOnce() -> W) -> usize {
+        assert!(
+            !self.frozen,
+            "Can not insert an object of type {} into a frozen workspace",
+            std::any::type_name::<W>()
+        );
+        #[cfg(all(feature = "strict-tags", debug_assertions))]
+        self.assert_not_only_keyed::<W>();
+        // Create the object before touching the workspace, so that a panic in
+        // `create` leaves the workspace untouched
+        let w = create();
+        let idx = self.workspaces.len();
+        #[allow(unused_mut)]
+        let mut entry = Entry::new(w);
+        #[cfg(feature = "debug-backtrace")]
+        {
+            entry.creation_backtrace = Some(Box::new(std::backtrace::Backtrace::force_capture()));
+        }
+        self.push_entry(entry);
+        idx
+    }
+
+    /// Checks that objects of type `W` are not exclusively stored under keys, in which case an
+    /// unkeyed access most likely was meant to be keyed.
+    #[cfg(all(feature = "strict-tags", debug_assertions))]
+    fn assert_not_only_keyed<W: 'static>(&self) {
+        let keyed = self
+            .workspaces
+            .iter()
+            .any(|entry| matches!(entry.keyed_value, Some((id, _)) if id == TypeId::of::<W>()));
+        assert!(
+            !keyed,
+            "Requested an unkeyed object of type {}, which is only stored under a key. \
+             Use `Workspace::get_keyed` to access the keyed object.",
+            std::any::type_name::<W>()
+        );
+    }
+
+    /// Appends a new entry, which must not have the same type as any existing entry.
+    fn push_entry(&mut self, mut entry: Entry) -> &mut Entry {
+        entry.last_access = self.generation;
+        #[cfg(feature = "ttl")]
+        {
+            entry.last_access_time = Some(self.now());
+        }
+        #[cfg(feature = "metrics")]
+        self.record_creation_stats(entry.value_type_id());
+        self.workspaces.push(entry);
+        #[cfg(feature = "warn-on-many-types")]
+        self.check_many_types();
+        #[cfg(debug_assertions)]
+        self.validate_last();
+        self.notify_created(self.workspaces.len() - 1);
+        self.workspaces.last_mut().unwrap()
+    }
+
+    /// Checks that the last entry, which has just been pushed, upholds the internal invariants
+    /// of the workspace.
+    ///
+    /// Lookups assume that there is at most one entry per type, so storing two entries of the
+    /// same type would silently shadow one of them. Since all other entries have been checked
+    /// when they were pushed, it suffices to compare the new entry against them, which keeps
+    /// inserting many types linear in debug builds.
+    #[cfg(debug_assertions)]
+    fn validate_last(&self) {
+        let (entry, others) = self.workspaces.split_last().unwrap();
+        let type_id = entry.value_type_id();
+        assert!(
+            !others.iter().any(|other| other.value_type_id() == type_id),
+            "Internal error: Workspace contains multiple entries of the same type: {:?}",
+            self.workspaces
+        );
+        if let Some((value_type, value)) = entry.keyed_value {
+            assert!(
+                value_type != type_id && (*value(&*entry.value)).type_id() == value_type,
+                "Internal error: Keyed entry {:?} does not wrap a value of the recorded type",
+                entry
+            );
+        }
+    }
+
+    fn find_index_of<W: 'static>(&self) -> Option<usize> {
+        // Note: We treat the Vec as a stack, so we search from the end of the vector.
+        self.workspaces.iter().rposition(|ws| ws.is::<W>())
+    }
+}
+
+/// A handle to an object of type `W` stored in a [`Workspace`].
+///
+/// The handle caches the position of the object inside the workspace, so that repeated access
+/// through [`WorkspaceHandle::get`] avoids the search (and reordering) performed by
+/// [`Workspace::get_or_default`].
+///
+/// ## Invalidation
+///
+/// A handle is invalidated whenever the object changes position in the workspace. This may
+/// happen when
+///
+/// - another object is accessed through one of the `get_or_*` methods, since the accessed
+///   object is moved into the most-recently-used position, which shifts all objects behind
+///   it, possibly including the object referred to by the handle,
+/// - objects are removed, e.g. with [`Workspace::clear`] or [`Workspace::extract_if`].
+///
+/// Using an invalidated handle is not unsafe: [`WorkspaceHandle::get`] verifies in O(1) that
+/// the cached position still holds an object of type `W`, and returns `None` otherwise. Since a
+/// workspace holds at most one object per type, a successful check guarantees that the
+/// returned object is the correct one. A handle may however also be used with a different
+/// workspace than the one that created it, in which case it can succeed if the object of type
+/// `W` happens to be at the same position.
+pub struct WorkspaceHandle<W> {
+    index: usize,
+    marker: PhantomData<fn() -> W>,
+}
+
+impl<W: 'static> WorkspaceHandle<W> {
+    /// Returns the object that this handle refers to, or `None` if the handle is invalidated.
+    pub fn get<'a>(&self, workspace: &'a mut Workspace) -> Option<&'a mut W> {
+        workspace
+            .workspaces
+            .get_mut(self.index)
+            .and_then(Entry::downcast_mut)
+    }
+}
+
+impl<W> Clone for WorkspaceHandle<W> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<W> Copy for WorkspaceHandle<W> {}
+
+impl<W> fmt::Debug for WorkspaceHandle<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorkspaceHandle")
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+/// The objects of a [`Workspace`] other than the one borrowed by [`Workspace::split_off`].
+#[derive(Debug)]
+pub struct WorkspaceRest<'a> {
+    entries: &'a mut [Entry],
+}
+
+impl WorkspaceRest<'_> {
+    /// Returns the object of type `W`, if present.
+    ///
+    /// Returns `None` for the type of the object borrowed by [`Workspace::split_off`].
+    pub fn get_mut<W: 'static>(&mut self) -> Option<&mut W> {
+        self.entries.iter_mut().rev().find_map(Entry::downcast_mut)
+    }
+
+    /// Returns the object of type `W`, if present.
+    ///
+    /// Returns `None` for the type of the object borrowed by [`Workspace::split_off`].
+    pub fn try_get<W: 'static>(&self) -> Option<&W> {
+        self.entries.iter().rev().find_map(Entry::downcast_ref)
+    }
+
+    /// Returns the number of objects accessible through the rest.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no objects are accessible through the rest.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// An operation that can be applied to stored objects with [`Workspace::for_each_registered`].
+///
+/// ```rust
+/// use davenport::{Workspace, WorkspaceOp};
+///
+/// struct CountElements(usize);
+///
+/// impl WorkspaceOp for CountElements {
+///     type Target = [u32];
+///
+///     fn apply(&mut self, target: &mut [u32]) {
+///         self.0 += target.len();
+///     }
+/// }
+///
+/// let mut ws = Workspace::default();
+/// ws.register_as::<Vec<u32>, [u32]>(|v| v.as_mut_slice());
+/// ws.get_or_default::<Vec<u32>>().extend([1, 2, 3]);
+///
+/// let mut count = CountElements(0);
+/// ws.for_each_registered(&mut count);
+/// assert_eq!(count.0, 3);
+/// ```
+pub trait WorkspaceOp {
+    /// The type that objects are converted to before the operation is applied, typically a
+    /// trait object.
+    type Target: ?Sized + 'static;
+
+    /// Applies the operation to a single object.
+    fn apply(&mut self, target: &mut Self::Target);
+}
+
+/// Types that can report their current capacity.
+///
+/// This is used to track capacity high-water marks with
+/// [`Workspace::get_or_default_tracked`]. The unit of capacity is up to the implementor,
+/// but is typically a number of elements.
+pub trait CapacityReport {
+    /// Returns the current capacity.
+    fn capacity(&self) -> usize;
+}
+
+impl<T> CapacityReport for Vec<T> {
+    fn capacity(&self) -> usize {
+        Vec::capacity(self)
+    }
+}
+
+impl<T> CapacityReport for VecDeque<T> {
+    fn capacity(&self) -> usize {
+        VecDeque::capacity(self)
+    }
+}
+
+impl CapacityReport for String {
+    fn capacity(&self) -> usize {
+        String::capacity(self)
+    }
+}
+
+fn report_capacity<W: 'static + CapacityReport>(any: &dyn Any) -> usize {
+    any.downcast_ref::<W>().map_or(0, W::capacity)
+}
+
+/// Types that can be constructed with a given capacity.
+///
+/// This is the counterpart of [`CapacityReport`], used by [`WorkspaceBuilder`] to reconstruct
+/// objects from a capacity snapshot.
+pub trait WithCapacity {
+    /// Constructs a new object with at least the given capacity.
+    fn with_capacity(capacity: usize) -> Self;
+}
+
+impl<T> WithCapacity for Vec<T> {
+    fn with_capacity(capacity: usize) -> Self {
+        Vec::with_capacity(capacity)
+    }
+}
+
+impl<T> WithCapacity for VecDeque<T> {
+    fn with_capacity(capacity: usize) -> Self {
+        VecDeque::with_capacity(capacity)
+    }
+}
+
+impl WithCapacity for String {
+    fn with_capacity(capacity: usize) -> Self {
+        String::with_capacity(capacity)
+    }
+}
+
+/// Types that can be constructed from a context of type `Ctx`, for use with
+/// [`Workspace::get_or_build`].
+///
+/// ```rust
+/// use davenport::{BuildFromContext, Workspace};
+///
+/// struct Config {
+///     chunk_size: usize,
+/// }
+///
+/// struct ChunkBuffer(Vec<u8>);
+///
+/// impl BuildFromContext<Config> for ChunkBuffer {
+///     fn build(config: &Config) -> Self {
+///         ChunkBuffer(Vec::with_capacity(config.chunk_size))
+///     }
+/// }
+///
+/// let mut ws = Workspace::default();
+/// let buffer: &mut ChunkBuffer = ws.get_or_build(&Config { chunk_size: 64 });
+/// assert!(buffer.0.capacity() >= 64);
+/// ```
+pub trait BuildFromContext<Ctx: ?Sized> {
+    /// Constructs a new object from the given context.
+    fn build(ctx: &Ctx) -> Self;
+}
+
+/// Types that can reserve capacity ahead of use, for use with
+/// [`Workspace::get_or_default_reserving`].
+///
+/// Implementing this trait for a composite workspace type lets it pre-size all of its internal
+/// buffers from a single hint.
+///
+/// ```rust
+/// use davenport::{Workspace, WorkspaceReserve};
+///
+/// #[derive(Default)]
+/// struct SortWorkspace {
+///     keys: Vec<u64>,
+///     permutation: Vec<usize>,
+/// }
+///
+/// impl WorkspaceReserve for SortWorkspace {
+///     fn reserve_for(&mut self, hint: usize) {
+///         self.keys.reserve_for(hint);
+///         self.permutation.reserve_for(hint);
+///     }
+/// }
+///
+/// let mut ws = Workspace::default();
+/// let workspace: &mut SortWorkspace = ws.get_or_default_reserving(100);
+/// assert!(workspace.keys.capacity() >= 100);
+/// ```
+pub trait WorkspaceReserve {
+    /// Makes sure that the object can hold at least `hint` elements without reallocating.
+    ///
+    /// The meaning of an element is up to the implementor.
+    fn reserve_for(&mut self, hint: usize);
+}
+
+impl<T> WorkspaceReserve for Vec<T> {
+    fn reserve_for(&mut self, hint: usize) {
+        self.reserve(hint.saturating_sub(self.len()));
+    }
+}
+
+impl<T> WorkspaceReserve for VecDeque<T> {
+    fn reserve_for(&mut self, hint: usize) {
+        self.reserve(hint.saturating_sub(self.len()));
+    }
+}
+
+impl WorkspaceReserve for String {
+    fn reserve_for(&mut self, hint: usize) {
+        self.reserve(hint.saturating_sub(self.len()));
+    }
+}
+
+/// Objects that need access to other workspace objects when they are removed from a workspace.
+///
+/// Types implementing this trait must be registered with [`Workspace::register_teardown`].
+/// When the workspace is cleared or dropped, [`Teardown::teardown`] is then called for the
+/// registered object right before it is dropped, with access to the objects that have not been
+/// dropped yet. See [`Workspace::clear`] for the order in which objects are dropped.
+///
+/// Teardown only runs when the workspace is cleared or dropped. Objects removed individually,
+/// e.g. by [`Workspace::invalidate`], by eviction under a
+/// [memory budget](Workspace::set_memory_budget), by `Workspace::evict_older_than` with the
+/// `ttl` feature or by [`AfterUse::Drop`], are dropped without teardown, since these removals
+/// happen while the workspace is in the middle of another operation and can not be lent out.
+///
+/// ```rust
+/// use davenport::{Teardown, Workspace};
+///
+/// struct Log(Vec<String>);
+///
+/// impl Teardown for Log {
+///     fn teardown(&mut self, workspace: &mut Workspace) {
+///         // Flush using scratch space from the same workspace
+///         let scratch = workspace.get_or_default::<String>();
+///         for line in &self.0 {
+///             scratch.push_str(line);
+///         }
+///     }
+/// }
+///
+/// let mut ws = Workspace::default();
+/// ws.register_teardown::<Log>();
+/// ws.get_or_insert_with(|| Log(vec!["done".to_string()]));
+/// ws.clear();
+/// ```
+pub trait Teardown {
+    /// Prepares the object for being dropped.
+    ///
+    /// Objects inserted into the workspace during teardown are dropped as well, so teardown
+    /// must not insert objects whose own teardown again inserts objects indefinitely.
+    fn teardown(&mut self, workspace: &mut Workspace);
+}
+
+/// Describes how to prewarm a single type with [`Workspace::prewarm`].
+///
+/// ```rust
+/// use davenport::{PrewarmSpec, Workspace};
+///
+/// let mut ws = Workspace::default();
+/// ws.prewarm([
+///     PrewarmSpec::reserving::<Vec<f64>>(1000),
+///     PrewarmSpec::reserving::<String>(64),
+///     PrewarmSpec::with(|| [0u8; 256]),
+/// ]);
+/// assert!(ws.try_get::<Vec<f64>>().unwrap().capacity() >= 1000);
+/// ```
+pub struct PrewarmSpec {
+    apply: Box<dyn FnOnce(&mut Workspace)>,
+}
+
+impl PrewarmSpec {
+    /// Inserts the object returned by `create`, unless an object of type `W` is present.
+    pub fn with<W: 'static>(create: impl 'static + FnOnce() -> W) -> Self {
+        Self {
+            apply: Box::new(|ws| {
+                ws.get_or_insert_with(create);
+            }),
+        }
+    }
+
+    /// Inserts a default-constructed object of type `W` if not present, and makes sure that
+    /// it can hold at least `hint` elements, see [`Workspace::get_or_default_reserving`].
+    pub fn reserving<W: 'static + Default + WorkspaceReserve>(hint: usize) -> Self {
+        Self {
+            apply: Box::new(move |ws| {
+                ws.get_or_default_reserving::<W>(hint);
+            }),
+        }
+    }
+}
+
+impl fmt::Debug for PrewarmSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrewarmSpec").finish_non_exhaustive()
+    }
+}
+
+/// Reconstructs workspaces from a snapshot obtained with [`Workspace::capacities_snapshot`].
+///
+/// Each type that should be reconstructed must be registered with
+/// [`WorkspaceBuilder::register`]. Entries of the snapshot whose type has not been registered
+/// are skipped.
+///
+/// ```rust
+/// use davenport::{Workspace, WorkspaceBuilder};
+///
+/// let mut ws = Workspace::default();
+/// ws.get_or_default_tracked::<Vec<u32>>().reserve(1000);
+/// let snapshot = ws.capacities_snapshot();
+///
+/// let mut warm = WorkspaceBuilder::new()
+///     .register::<Vec<u32>>()
+///     .build(&snapshot);
+/// assert!(warm.get_or_default::<Vec<u32>>().capacity() >= 1000);
+/// ```
+#[derive(Debug, Default)]
+pub struct WorkspaceBuilder {
+    constructors: Vec<(TypeId, EntryConstructor)>,
+}
+
+type EntryConstructor = fn(usize) -> Entry;
+
+impl WorkspaceBuilder {
+    /// Creates a builder without any registered types.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a type that can be reconstructed from a capacity snapshot.
+    pub fn register<W>(mut self) -> Self
+    where
+        W: 'static + CapacityReport + WithCapacity,
+    {
+        self.constructors
+            .push((TypeId::of::<W>(), construct_with_capacity::<W>));
+        self
+    }
+
+    /// Builds a workspace containing objects preallocated according to the given capacities.
+    ///
+    /// The reconstructed objects have their capacity tracked, as if they had been accessed
+    /// with [`Workspace::get_or_default_tracked`].
+    pub fn build(&self, capacities: &[(TypeId, usize)]) -> Workspace {
+        let mut workspace = Workspace::new();
+        for &(type_id, capacity) in capacities {
+            let already_present = workspace
+                .workspaces
+                .iter()
+                .any(|ws| ws.value_type_id() == type_id);
+            let constructor = self.constructors.iter().find(|(id, _)| *id == type_id);
+            if let (false, Some((_, construct))) = (already_present, constructor) {
+                workspace.push_entry(construct(capacity));
+            }
+        }
+        workspace
+    }
+}
+
+fn construct_with_capacity<W>(capacity: usize) -> Entry
+where
+    W: 'static + CapacityReport + WithCapacity,
+{
+    let mut entry = Entry::new(W::with_capacity(capacity));
+    entry.capacity_report = Some(report_capacity::<W>);
+    entry.record_access();
+    entry
+}
+
+/// A workspace that falls back to a parent workspace before creating new entries.
+///
+/// This supports a scoping model in which buffers belonging to an outer scope are shared with
+/// inner scopes, while types that are only used in the inner scope stay local to it.
+///
+/// ## Borrow semantics
+///
+/// The layered workspace holds a *mutable* borrow of its parent for its entire lifetime `'a`.
+/// Consequently the parent can not be accessed directly while the layered workspace is alive,
+/// and references handed out by [`LayeredWorkspace::get_or_default`] may point into either
+/// the local workspace or the parent. New entries are only ever created in the local workspace,
+/// and objects found in the parent are looked up without counting as an access, as with
+/// [`Workspace::try_get_mut`]. The parent is therefore never modified by a lookup other than
+/// through mutation of the returned reference: its internal order, generation and statistics
+/// are left as they are, and its memory budget is not enforced. Once the layered workspace is
+/// dropped, the local entries are dropped with it (unless retrieved with
+/// [`LayeredWorkspace::into_local`]), and the parent is usable again.
+#[derive(Debug)]
+pub struct LayeredWorkspace<'a> {
+    local: Workspace,
+    parent: Option<&'a mut Workspace>,
+}
+
+impl<'a> LayeredWorkspace<'a> {
+    /// Creates a new layered workspace with an empty local workspace.
+    pub fn new(parent: Option<&'a mut Workspace>) -> Self {
+        Self {
+            local: Workspace::default(),
+            parent,
+        }
+    }
+
+    /// Returns a reference to the local workspace.
+    pub fn local(&self) -> &Workspace {
+        &self.local
+    }
+
+    /// Returns a mutable reference to the local workspace.
+    pub fn local_mut(&mut self) -> &mut Workspace {
+        &mut self.local
+    }
+
+    /// Consumes the layered workspace, returning the local workspace.
+    pub fn into_local(self) -> Workspace {
+        self.local
+    }
+
+    /// Returns an object of type `W`, looking first in the local workspace and then in the
+    /// parent workspace.
+    ///
+    /// If neither contains an object of type `W`, a default-constructed object is inserted
+    /// into the local workspace.
+    pub fn get_or_default<W>(&mut self) -> &mut W
+    where
+        W: 'static + Default,
+    {
+        let local_idx = self.local.find_index_of::<W>();
+        if local_idx.is_none() {
+            if let Some(parent) = self.parent.as_deref_mut() {
+                if let Some(w) = parent.try_get_mut() {
+                    return w;
+                }
+            }
+        }
+        self.local
+            .get_or_insert_entry_at(local_idx, W::default)
+            .downcast_mut()
+            .expect("Internal error: Downcasting can by definition not fail")
+    }
+}
 
 /// Runs the provided closure with the thread-local workspace as an argument.
 ///
-/// This simplifies working with [`Workspace`] when it's stored as a thread-local variable.
+/// This simplifies working with [`Workspace`] when it's stored as a thread-local variable.
+///
+/// Note that the typed workspace must have a [`Default`] implementation.
+///
+/// After the closure returns, the [`MemoryPolicy`] attached to the typed workspace, if any,
+/// is applied.
+///
+/// See the [crate-level documentation](`crate`) for typical usage examples.
+///
+/// ## Panics
+///
+/// Panics if used recursively with the same workspace variable, as it relies on
+/// mutably borrowing through [`RefCell`](`std::cell::RefCell`). See the crate-level
+/// documentation for a discussion of this limitation.
+pub fn with_thread_local_workspace<W: 'static + Default, T>(
+    workspace: impl WorkspaceKey,
+    f: impl FnOnce(&mut W) -> T,
+) -> T {
+    with_borrowed_workspace(workspace, |type_erased_workspace| {
+        let workspace = type_erased_workspace.get_or_default();
+        let result = f(workspace);
+        type_erased_workspace.apply_memory_policy_of_last();
+        result
+    })
+}
+
+/// Same as [`with_thread_local_workspace`], but moves the typed workspace into the closure, and
+/// stores the object returned by the closure in its place.
+///
+/// This lets the closure temporarily own the object, e.g. to hand it to an API that takes
+/// ownership, or to return its contents to the caller while leaving a replacement behind.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_thread_local_workspace_take};
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// fn squares(n: u64) -> Vec<u64> {
+///     with_thread_local_workspace_take(&WORKSPACE, |mut buffer: Vec<u64>| {
+///         buffer.clear();
+///         buffer.extend((0..n).map(|i| i * i));
+///         // Return the filled buffer to the caller, and cache an allocation of the same size
+///         let replacement = Vec::with_capacity(buffer.capacity());
+///         (replacement, buffer)
+///     })
+/// }
+///
+/// assert_eq!(squares(4), [0, 1, 4, 9]);
+/// ```
+///
+/// ## Panics
+///
+/// Panics if used recursively with the same workspace variable. If the closure panics, the
+/// object moved into it is lost, and a default-constructed object is left in its place.
+pub fn with_thread_local_workspace_take<W: 'static + Default, T>(
+    workspace: impl WorkspaceKey,
+    f: impl FnOnce(W) -> (W, T),
+) -> T {
+    with_borrowed_workspace(workspace, |type_erased_workspace| {
+        let slot = type_erased_workspace.get_or_default::<W>();
+        let (w, result) = f(std::mem::take(slot));
+        *slot = w;
+        result
+    })
+}
+
+/// Returns an iterator that lazily produces values using a typed workspace of a thread-local
+/// workspace variable.
+///
+/// The closure-based API of [`with_thread_local_workspace`] ends access to the workspace when
+/// the closure returns, which forces eager materialization of results computed from a reused
+/// buffer. Instead, `build` prepares the typed workspace and returns a step function, which the
+/// iterator calls with the typed workspace to produce each item, until it returns `None`.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, thread_local_workspace_iter};
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// fn running_sums(values: &[u32]) -> impl Iterator<Item = u32> + '_ {
+///     thread_local_workspace_iter(&WORKSPACE, move |buffer: &mut Vec<u32>| {
+///         buffer.clear();
+///         buffer.extend_from_slice(values);
+///         let mut idx = 0;
+///         let mut sum = 0;
+///         move |buffer: &mut Vec<u32>| {
+///             let value = *buffer.get(idx)?;
+///             idx += 1;
+///             sum += value;
+///             Some(sum)
+///         }
+///     })
+/// }
+///
+/// assert_eq!(running_sums(&[1, 2, 3]).collect::<Vec<_>>(), [1, 3, 6]);
+/// ```
+///
+/// ## Reentrancy
+///
+/// A borrow of a thread-local variable can not outlive the access to it, so the iterator can
+/// not hold on to the workspace variable itself. Instead, the typed workspace is taken out of
+/// the workspace for the lifetime of the iterator, like with
+/// [`with_thread_local_workspace_take`], and put back when the iterator is dropped. The
+/// workspace variable therefore remains usable while the iterator is alive, but any use of
+/// the same type in the meantime, including a second iterator, gets a default-constructed
+/// object, whose allocations are lost when the original is put back. If the workspace
+/// variable is borrowed when the iterator is dropped, e.g. because the iterator is dropped
+/// inside [`with_thread_local_workspace`], the typed workspace is dropped instead.
+///
+/// ## Panics
+///
+/// Panics if the workspace variable is in use when the iterator is created.
+pub fn thread_local_workspace_iter<W, I, K>(
+    workspace: K,
+    build: impl FnOnce(&mut W) -> I,
+) -> WorkspaceIter<W, I, K>
+where
+    W: 'static + Default,
+    K: WorkspaceKey,
+{
+    let mut w = with_borrowed_workspace(&workspace, |type_erased_workspace| {
+        std::mem::take(type_erased_workspace.get_or_default::<W>())
+    });
+    let step = build(&mut w);
+    WorkspaceIter {
+        key: workspace,
+        workspace: Some(w),
+        step,
+    }
+}
+
+/// Iterator returned by [`thread_local_workspace_iter`].
+///
+/// Puts the typed workspace back into the thread-local workspace when dropped.
+pub struct WorkspaceIter<W, I, K = &'static LocalKey<RefCell<Workspace>>>
+where
+    W: 'static + Default,
+    K: WorkspaceKey,
+{
+    key: K,
+    // Only `None` while being dropped
+    workspace: Option<W>,
+    step: I,
+}
+
+impl<W, I, K, T> Iterator for WorkspaceIter<W, I, K>
+where
+    W: 'static + Default,
+    I: FnMut(&mut W) -> Option<T>,
+    K: WorkspaceKey,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let w = self
+            .workspace
+            .as_mut()
+            .expect("Internal error: Typed workspace is present until the iterator is dropped");
+        (self.step)(w)
+    }
+}
+
+impl<W: 'static + Default, I, K: WorkspaceKey> Drop for WorkspaceIter<W, I, K> {
+    fn drop(&mut self) {
+        if let Some(w) = self.workspace.take() {
+            // The thread-local variable may already be destroyed, or the workspace borrowed
+            let _ = self.key.try_with_cell(|refcell_ws| {
+                if let Ok(mut type_erased_workspace) = refcell_ws.try_borrow_mut() {
+                    *type_erased_workspace.get_or_default::<W>() = w;
+                }
+            });
+        }
+    }
+}
+
+impl<W: 'static + Default, I, K: WorkspaceKey> fmt::Debug for WorkspaceIter<W, I, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorkspaceIter")
+            .field("type_name", &std::any::type_name::<W>())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Determines what happens to a typed workspace after use with
+/// [`with_thread_local_workspace_controlled`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AfterUse {
+    /// The object is kept as-is for the next use, which is the behavior of
+    /// [`with_thread_local_workspace`].
+    #[default]
+    Keep,
+    /// The object is replaced by a default-constructed object.
+    Reset,
+    /// The object is removed from the workspace, and dropped.
+    Drop,
+}
+
+/// Same as [`with_thread_local_workspace`], but lets the closure decide what happens to the
+/// typed workspace after use.
 ///
-/// Note that the typed workspace must have a [`Default`] implementation.
+/// The closure returns its result together with an [`AfterUse`], which is applied after the
+/// closure returns. This allows the decision to depend on the result, e.g. to discard a buffer
+/// that grew unusually large, or that may hold invalid state after a failed computation.
 ///
-/// See the [crate-level documentation](`crate`) for typical usage examples.
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_thread_local_workspace_controlled};
+/// use davenport::AfterUse;
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// let len = with_thread_local_workspace_controlled(&WORKSPACE, |buffer: &mut Vec<u8>| {
+///     buffer.resize(1 << 20, 0);
+///     let after = if buffer.len() > 1024 { AfterUse::Drop } else { AfterUse::Keep };
+///     (buffer.len(), after)
+/// });
+/// assert_eq!(len, 1 << 20);
+/// ```
 ///
 /// ## Panics
 ///
-/// Panics if used recursively with the same workspace variable, as it relies on
-/// mutably borrowing through [`RefCell`](`std::cell::RefCell`). See the crate-level documentation for
-/// a discussion of this limitation.
-pub fn with_thread_local_workspace<W: 'static + Default, T>(
-    workspace: &'static LocalKey<RefCell<Workspace>>,
+/// Panics if used recursively with the same workspace variable.
+pub fn with_thread_local_workspace_controlled<W: 'static + Default, T>(
+    workspace: impl WorkspaceKey,
+    f: impl FnOnce(&mut W) -> (T, AfterUse),
+) -> T {
+    with_borrowed_workspace(workspace, |type_erased_workspace| {
+        let (result, after_use) = f(type_erased_workspace.get_or_default());
+        match after_use {
+            AfterUse::Keep => type_erased_workspace.apply_memory_policy_of_last(),
+            AfterUse::Reset => {
+                if let Some(w) = type_erased_workspace.try_get_mut::<W>() {
+                    *w = W::default();
+                }
+            }
+            AfterUse::Drop => {
+                type_erased_workspace.invalidate::<W>();
+            }
+        }
+        result
+    })
+}
+
+/// Same as [`with_thread_local_workspace`], but falls back to a temporary workspace instead of
+/// panicking if the thread-local workspace is already borrowed.
+///
+/// In single-threaded async runtimes, several tasks run interleaved on the same thread. If one
+/// task holds on to the thread-local workspace across a yield point, for example through a
+/// guard that borrows the `RefCell` directly, another task using the same workspace variable
+/// would otherwise panic. With this function, the second task instead receives a temporary
+/// default-constructed object, which is dropped after the closure returns. The same applies
+/// to recursive use.
+///
+/// The fallback sacrifices reuse for robustness, so it should be the exception rather than
+/// the rule. Since each level of recursion beyond the first creates another temporary
+/// object, the number of nested fallbacks can be bounded with [`set_max_reentrant_depth`].
+///
+/// ## Panics
+///
+/// Panics if falling back would exceed the depth set with [`set_max_reentrant_depth`].
+pub fn with_thread_local_workspace_async_safe<W: 'static + Default, T>(
+    workspace: impl WorkspaceKey,
     f: impl FnOnce(&mut W) -> T,
 ) -> T {
-    workspace.with(|refcell_ws| {
-        let mut type_erased_workspace = refcell_ws.try_borrow_mut().expect(
-            "Internal error: Can not recursively use the same workspace variable. \
-                     See discussion on limitations in davenport's crate-level documentation.",
+    workspace.with_cell(|refcell_ws| match refcell_ws.try_borrow_mut() {
+        Ok(mut type_erased_workspace) => {
+            let result = f(type_erased_workspace.get_or_default());
+            type_erased_workspace.apply_memory_policy_of_last();
+            result
+        }
+        Err(_) => {
+            let _depth = FallbackDepth::enter();
+            f(&mut W::default())
+        }
+    })
+}
+
+static MAX_REENTRANT_DEPTH: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+thread_local! {
+    static REENTRANT_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Sets the maximum number of nested fallbacks to temporary workspaces in
+/// [`with_thread_local_workspace_async_safe`].
+///
+/// The depth counts the fallbacks currently active on a thread, across all workspace
+/// variables. Exceeding it panics instead of creating yet another temporary object, which
+/// turns runaway recursion into a clear error rather than unbounded memory use. The maximum
+/// applies to all threads, and is unbounded by default.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, set_max_reentrant_depth};
+/// use davenport::with_thread_local_workspace_async_safe;
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// fn depth(n: usize) -> usize {
+///     with_thread_local_workspace_async_safe(&WORKSPACE, |_: &mut Vec<u32>| {
+///         if n == 0 { 0 } else { 1 + depth(n - 1) }
+///     })
+/// }
+///
+/// set_max_reentrant_depth(8);
+/// assert_eq!(depth(8), 8);
+/// assert!(std::panic::catch_unwind(|| depth(9)).is_err());
+/// ```
+pub fn set_max_reentrant_depth(max_depth: usize) {
+    MAX_REENTRANT_DEPTH.store(max_depth, Ordering::Relaxed);
+}
+
+/// Tracks an active fallback to a temporary workspace for the duration of its lifetime.
+struct FallbackDepth;
+
+impl FallbackDepth {
+    fn enter() -> Self {
+        let max_depth = MAX_REENTRANT_DEPTH.load(Ordering::Relaxed);
+        REENTRANT_DEPTH.with(|depth| {
+            assert!(
+                depth.get() < max_depth,
+                "Exceeded the maximum reentrant workspace depth of {}.",
+                max_depth
+            );
+            depth.set(depth.get() + 1);
+        });
+        Self
+    }
+}
+
+impl Drop for FallbackDepth {
+    fn drop(&mut self) {
+        REENTRANT_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// Returns `true` if the workspace variable is currently in use on this thread.
+///
+/// Using the workspace variable while this returns `true`, e.g. with
+/// [`with_thread_local_workspace`], panics. Code that may be re-entered can check this
+/// beforehand to choose a fallback path instead. The check does not itself borrow the
+/// workspace.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_thread_local_workspace};
+/// use davenport::is_thread_local_workspace_borrowed;
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// fn buffered_len(values: &[u32]) -> usize {
+///     if is_thread_local_workspace_borrowed(&WORKSPACE) {
+///         values.to_vec().len()
+///     } else {
+///         with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| {
+///             buffer.clear();
+///             buffer.extend_from_slice(values);
+///             buffer.len()
+///         })
+///     }
+/// }
+///
+/// let len = with_thread_local_workspace(&WORKSPACE, |_: &mut Vec<u32>| buffered_len(&[1, 2]));
+/// assert_eq!(len, 2);
+/// ```
+pub fn is_thread_local_workspace_borrowed(workspace: impl WorkspaceKey) -> bool {
+    workspace.with_cell(|refcell_ws| refcell_ws.try_borrow().is_err())
+}
+
+/// Same as [`with_thread_local_workspace`], but attaches the given memory policy to the typed
+/// workspace if it is newly created.
+///
+/// See [`Workspace::get_or_default_with_policy`] for details.
+///
+/// ## Panics
+///
+/// Panics if used recursively with the same workspace variable.
+pub fn with_thread_local_workspace_with_policy<W, T>(
+    workspace: impl WorkspaceKey,
+    policy: MemoryPolicy,
+    f: impl FnOnce(&mut W) -> T,
+) -> T
+where
+    W: 'static + Default + Shrinkable,
+{
+    with_borrowed_workspace(workspace, |type_erased_workspace| {
+        let workspace = type_erased_workspace.get_or_default_with_policy(policy);
+        let result = f(workspace);
+        type_erased_workspace.apply_memory_policy_of_last();
+        result
+    })
+}
+
+/// Runs the provided closure with a slice of length `len` from the thread-local workspace,
+/// and caps the retained capacity of the backing buffer at `max_retained` elements afterwards.
+///
+/// See [`Workspace::get_capped_slice_or_default`].
+///
+/// ## Panics
+///
+/// Panics if used recursively with the same workspace variable.
+pub fn with_thread_local_capped_slice<T, R>(
+    workspace: impl WorkspaceKey,
+    len: usize,
+    max_retained: usize,
+    f: impl FnOnce(&mut [T]) -> R,
+) -> R
+where
+    T: 'static + Default + Clone,
+{
+    with_borrowed_workspace(workspace, |type_erased_workspace| {
+        let result = f(type_erased_workspace.get_capped_slice_or_default(len, max_retained));
+        type_erased_workspace.apply_memory_policy_of_last();
+        result
+    })
+}
+
+/// Same as [`with_thread_local_workspace`], but additionally passes a context value to the
+/// closure.
+///
+/// The context is moved into the closure invocation. This is equivalent to capturing the
+/// context in the closure, but can be more convenient in generated code.
+///
+/// ## Panics
+///
+/// Panics if used recursively with the same workspace variable.
+pub fn with_thread_local_workspace_ctx<W: 'static + Default, C, T>(
+    workspace: impl WorkspaceKey,
+    ctx: C,
+    f: impl FnOnce(&mut W, C) -> T,
+) -> T {
+    with_thread_local_workspace(workspace, |w| f(w, ctx))
+}
+
+/// Same as [`with_thread_local_workspace`], but passes objects of two distinct types to the
+/// closure.
+///
+/// The thread-local workspace is only borrowed once, so unlike nested calls to
+/// [`with_thread_local_workspace`] with the same workspace variable, this does not panic.
+/// See [`Workspace::get_pair_mut`]. After the closure returns, the [`MemoryPolicy`] attached to
+/// each of the two objects, if any, is applied.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_thread_local_workspace_pair};
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// let n = with_thread_local_workspace_pair(&WORKSPACE, |a: &mut Vec<u32>, b: &mut String| {
+///     a.push(1);
+///     b.push('x');
+///     a.len() + b.len()
+/// });
+/// assert_eq!(n, 2);
+/// ```
+///
+/// ## Panics
+///
+/// Panics if used recursively with the same workspace variable, or if `A` and `B` are the same
+/// type.
+pub fn with_thread_local_workspace_pair<A, B, T>(
+    workspace: impl WorkspaceKey,
+    f: impl FnOnce(&mut A, &mut B) -> T,
+) -> T
+where
+    A: 'static + Default,
+    B: 'static + Default,
+{
+    with_borrowed_workspace(workspace, |type_erased_workspace| {
+        let (a, b) = type_erased_workspace.get_pair_mut();
+        let result = f(a, b);
+        type_erased_workspace.apply_memory_policy::<A>();
+        type_erased_workspace.apply_memory_policy::<B>();
+        result
+    })
+}
+
+/// Runs the provided closure with the entire thread-local [`Workspace`] as an argument.
+///
+/// Every call to [`with_thread_local_workspace`] accesses thread-local storage and borrows the
+/// workspace through its `RefCell`. When many small operations need the workspace, it is
+/// cheaper to access it once with this function and perform all operations inside the closure,
+/// using e.g. [`Workspace::get_or_default`] or [`Workspace::handle`] to reach the objects.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_thread_local_workspace_raw};
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// let sum = with_thread_local_workspace_raw(&WORKSPACE, |ws| {
+///     (0..100u32)
+///         .map(|i| {
+///             let buffer = ws.get_or_default::<Vec<u32>>();
+///             buffer.push(i);
+///             buffer.len() as u32
+///         })
+///         .sum::<u32>()
+/// });
+/// assert_eq!(sum, 5050);
+/// ```
+///
+/// Note that memory policies are not applied automatically, since the closure may access any
+/// number of objects. Use [`Workspace::apply_memory_policy`] if needed.
+///
+/// ## Panics
+///
+/// Panics if used recursively with the same workspace variable.
+pub fn with_thread_local_workspace_raw<T>(
+    workspace: impl WorkspaceKey,
+    f: impl FnOnce(&mut Workspace) -> T,
+) -> T {
+    with_borrowed_workspace(workspace, f)
+}
+
+/// Runs the provided closure with the thread-local workspace cleared before and after.
+///
+/// Thread-local workspaces persist across tests that happen to run on the same thread, so
+/// buffers left behind by one test may be observed by another. Wrapping the body of a test in
+/// this function guarantees that the code under test starts with an empty workspace, and
+/// leaves nothing behind for later tests. The workspace is not borrowed while the closure
+/// runs, so the closure is free to use the workspace variable, e.g. through
+/// [`with_thread_local_workspace`].
+///
+/// ```rust
+/// use davenport::{
+///     define_thread_local_workspace, with_fresh_thread_local_workspace,
+///     with_thread_local_workspace,
+/// };
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// fn push_and_count(value: u32) -> usize {
+///     with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| {
+///         buffer.push(value);
+///         buffer.len()
+///     })
+/// }
+///
+/// assert_eq!(push_and_count(1), 1);
+/// assert_eq!(with_fresh_thread_local_workspace(&WORKSPACE, || push_and_count(2)), 1);
+/// ```
+///
+/// ## Panics
+///
+/// Panics if the workspace is currently borrowed, i.e. if called from within a closure
+/// passed to one of the other thread-local helpers with the same workspace variable.
+pub fn with_fresh_thread_local_workspace<T>(
+    workspace: impl WorkspaceKey,
+    f: impl FnOnce() -> T,
+) -> T {
+    with_borrowed_workspace(&workspace, Workspace::clear);
+    let result = f();
+    with_borrowed_workspace(&workspace, Workspace::clear);
+    result
+}
+
+/// Installs `value` as the object of type `W` in the thread-local workspace for the duration
+/// of the closure, and restores the previous object afterwards.
+///
+/// This mirrors `scoped-tls`: code run by the closure, e.g. through
+/// [`with_thread_local_workspace`], sees `value` instead of whatever object of type `W` was
+/// stored before. Once the closure returns or panics, the object it leaves behind is dropped,
+/// and the previous object is put back. If no object of type `W` was present before, none is
+/// present afterwards. The workspace is not borrowed while the closure runs.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, scoped_set_workspace_value};
+/// use davenport::with_thread_local_workspace;
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// fn current_precision() -> u32 {
+///     with_thread_local_workspace(&WORKSPACE, |precision: &mut u32| *precision)
+/// }
+///
+/// assert_eq!(current_precision(), 0);
+/// let inner = scoped_set_workspace_value(&WORKSPACE, 64u32, current_precision);
+/// assert_eq!(inner, 64);
+/// assert_eq!(current_precision(), 0);
+/// ```
+///
+/// ## Panics
+///
+/// Panics if the workspace is currently borrowed, i.e. if called from within a closure
+/// passed to one of the other thread-local helpers with the same workspace variable.
+pub fn scoped_set_workspace_value<W: 'static, T>(
+    workspace: impl WorkspaceKey,
+    value: W,
+    f: impl FnOnce() -> T,
+) -> T {
+    struct Restore<W: 'static, K: WorkspaceKey> {
+        key: K,
+        previous: Option<W>,
+    }
+
+    impl<W: 'static, K: WorkspaceKey> Drop for Restore<W, K> {
+        fn drop(&mut self) {
+            let previous = self.previous.take();
+            with_borrowed_workspace(&self.key, |ws| match previous {
+                Some(previous) => match ws.try_get_mut::<W>() {
+                    Some(current) => *current = previous,
+                    None => {
+                        let _ = ws.try_insert(previous);
+                    }
+                },
+                None => {
+                    ws.invalidate::<W>();
+                }
+            });
+        }
+    }
+
+    let previous = with_borrowed_workspace(&workspace, |ws| match ws.try_get_mut::<W>() {
+        Some(current) => Some(std::mem::replace(current, value)),
+        None => {
+            let _ = ws.try_insert(value);
+            None
+        }
+    });
+    let _restore = Restore {
+        key: workspace,
+        previous,
+    };
+    f()
+}
+
+/// Asserts that the thread-local workspace holds objects of exactly the given types.
+///
+/// This is intended for regression tests of code that should only ever use a known, bounded
+/// set of workspace types, so that e.g. a refactoring that introduces an additional cached
+/// type is caught. The order of `expected` is irrelevant. Objects stored under a key with
+/// [`Workspace::get_keyed`] are stored under an internal wrapper type, and thus never match
+/// the type of the object itself. Combine with [`with_fresh_thread_local_workspace`] to discard
+/// objects from previous use of the workspace.
+///
+/// ```rust
+/// use davenport::{assert_workspace_types, define_thread_local_workspace};
+/// use davenport::with_thread_local_workspace;
+/// use std::any::TypeId;
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| buffer.push(1));
+/// assert_workspace_types(&WORKSPACE, &[TypeId::of::<Vec<u32>>()]);
+/// ```
+///
+/// ## Panics
+///
+/// Panics if the set of stored types differs from `expected`, or if the workspace is
+/// currently borrowed.
+pub fn assert_workspace_types(workspace: impl WorkspaceKey, expected: &[TypeId]) {
+    with_borrowed_workspace(workspace, |workspace| {
+        let mut actual: Vec<_> = workspace
+            .workspaces
+            .iter()
+            .map(Entry::value_type_id)
+            .collect();
+        let mut expected = expected.to_vec();
+        actual.sort_unstable();
+        expected.sort_unstable();
+        expected.dedup();
+        assert!(
+            actual == expected,
+            "Workspace does not hold exactly the expected types. \
+             Expected: {:?}, stored objects: {:?}",
+            expected,
+            workspace.workspaces
         );
-        let workspace = type_erased_workspace.get_or_default();
-        f(workspace)
     })
 }
 
+/// Identifies a workspace variable.
+///
+/// The thread-local helpers such as [`with_thread_local_workspace`] accept any type
+/// implementing this trait. It is implemented for `&'static LocalKey<RefCell<Workspace>>`,
+/// which is what [`define_thread_local_workspace`] produces, for
+/// `GlobalWorkspace` with the `single-threaded` feature, and for
+/// references to keys. It can also be implemented for wrappers around such keys, e.g. in
+/// generated code.
+///
+/// Note that a generic `Borrow<LocalKey<_>>` bound would not suffice, since accessing a
+/// thread-local variable requires a `'static` reference to its key.
+pub trait WorkspaceKey {
+    /// Runs the provided closure with the cell holding the workspace of the current thread.
+    fn with_cell<T>(&self, f: impl FnOnce(&RefCell<Workspace>) -> T) -> T;
+
+    /// Same as [`WorkspaceKey::with_cell`], but returns `None` instead of panicking if the
+    /// workspace can no longer be accessed, e.g. because its thread-local variable has already
+    /// been destroyed.
+    fn try_with_cell<T>(&self, f: impl FnOnce(&RefCell<Workspace>) -> T) -> Option<T> {
+        Some(self.with_cell(f))
+    }
+}
+
+impl WorkspaceKey for &'static LocalKey<RefCell<Workspace>> {
+    fn with_cell<T>(&self, f: impl FnOnce(&RefCell<Workspace>) -> T) -> T {
+        self.with(f)
+    }
+
+    fn try_with_cell<T>(&self, f: impl FnOnce(&RefCell<Workspace>) -> T) -> Option<T> {
+        self.try_with(f).ok()
+    }
+}
+
+impl<K: WorkspaceKey + ?Sized> WorkspaceKey for &K {
+    fn with_cell<T>(&self, f: impl FnOnce(&RefCell<Workspace>) -> T) -> T {
+        (**self).with_cell(f)
+    }
+
+    fn try_with_cell<T>(&self, f: impl FnOnce(&RefCell<Workspace>) -> T) -> Option<T> {
+        (**self).try_with_cell(f)
+    }
+}
+
+fn with_borrowed_workspace<T>(key: impl WorkspaceKey, f: impl FnOnce(&mut Workspace) -> T) -> T {
+    match try_with_borrowed_workspace(key, f) {
+        Ok(result) => result,
+        Err(_) => panic!(
+            "Internal error: Can not recursively use the same workspace variable. \
+             See discussion on limitations in davenport's crate-level documentation."
+        ),
+    }
+}
+
+/// Same as [`with_borrowed_workspace`], but returns [`WorkspaceError::Reentrant`] instead of
+/// panicking if the workspace variable is already borrowed.
+fn try_with_borrowed_workspace<T>(
+    key: impl WorkspaceKey,
+    f: impl FnOnce(&mut Workspace) -> T,
+) -> Result<T, WorkspaceError> {
+    key.with_cell(|refcell_ws| {
+        let mut type_erased_workspace = refcell_ws
+            .try_borrow_mut()
+            .map_err(|_| WorkspaceError::Reentrant)?;
+        Ok(f(&mut type_erased_workspace))
+    })
+}
+
+/// Error returned when an object of a new type is requested from a frozen workspace.
+///
+/// See [`Workspace::freeze`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrozenWorkspaceError {
+    type_name: &'static str,
+}
+
+impl FrozenWorkspaceError {
+    /// The name of the type that could not be inserted.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+impl fmt::Display for FrozenWorkspaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "can not insert an object of type {} into a frozen workspace",
+            self.type_name
+        )
+    }
+}
+
+impl std::error::Error for FrozenWorkspaceError {}
+
+/// Error returned by [`catch_workspace`] when the wrapped closure panics.
+pub struct WorkspacePanic {
+    payload: Box<dyn Any + Send>,
+}
+
+impl WorkspacePanic {
+    /// Returns the panic message, if the panic payload is a string.
+    pub fn message(&self) -> Option<&str> {
+        self.payload
+            .downcast_ref::<&'static str>()
+            .copied()
+            .or_else(|| self.payload.downcast_ref::<String>().map(String::as_str))
+    }
+
+    /// Consumes the error, returning the panic payload.
+    pub fn into_payload(self) -> Box<dyn Any + Send> {
+        self.payload
+    }
+}
+
+impl fmt::Debug for WorkspacePanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorkspacePanic")
+            .field("message", &self.message())
+            .finish()
+    }
+}
+
+impl fmt::Display for WorkspacePanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.message() {
+            Some(message) => write!(f, "panic during workspace use: {}", message),
+            None => write!(f, "panic during workspace use"),
+        }
+    }
+}
+
+impl std::error::Error for WorkspacePanic {}
+
+/// Runs the provided closure, converting any panic into a [`WorkspacePanic`] error.
+///
+/// This is intended to wrap usage of [`with_thread_local_workspace`], for example when
+/// running untrusted closures. A panic caused by recursive use of the same workspace variable
+/// is caught like any other panic. Since the borrow of the thread-local workspace is released
+/// during unwinding, the workspace variable is guaranteed to be usable again once this
+/// function returns. The objects stored in the workspace may however have been left in
+/// whatever state the panicking closure left them in.
+pub fn catch_workspace<R>(f: impl FnOnce() -> R) -> Result<R, WorkspacePanic> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f))
+        .map_err(|payload| WorkspacePanic { payload })
+}
+
+/// An RAII guard for regions of code that must not be re-entered on the same thread.
+///
+/// The guard is built on a thread-local flag, which is set when the guard is created and reset
+/// when it is dropped. Creating a second guard for the same flag while the first is still alive
+/// panics, which makes it possible to catch latent reentry bugs close to their origin rather
+/// than deep inside a call to [`with_thread_local_workspace`].
+///
+/// ```rust
+/// use davenport::RecursionGuard;
+/// use std::cell::Cell;
+///
+/// thread_local! { static IN_SOLVER: Cell<bool> = const { Cell::new(false) }; }
+///
+/// fn solve() {
+///     let _guard = RecursionGuard::enter(&IN_SOLVER);
+///     // ... code that must not call `solve` again
+/// }
+///
+/// solve();
+/// solve();
+/// ```
+#[derive(Debug)]
+pub struct RecursionGuard {
+    flag: &'static LocalKey<Cell<bool>>,
+}
+
+impl RecursionGuard {
+    /// Enters the guarded region, returning `None` if it has already been entered on this
+    /// thread.
+    pub fn try_enter(flag: &'static LocalKey<Cell<bool>>) -> Option<Self> {
+        let already_entered = flag.with(|entered| entered.replace(true));
+        (!already_entered).then(|| Self { flag })
+    }
+
+    /// Enters the guarded region.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the region has already been entered on this thread.
+    pub fn enter(flag: &'static LocalKey<Cell<bool>>) -> Self {
+        Self::try_enter(flag).expect("Guarded region was re-entered recursively.")
+    }
+}
+
+impl Drop for RecursionGuard {
+    fn drop(&mut self) {
+        self.flag.with(|entered| entered.set(false));
+    }
+}
+
+/// Asserts, in debug builds only, that the given thread-local workspace is not currently in use.
+///
+/// This can be placed at the start of a function that uses the workspace in order to catch
+/// recursive use early. In release builds, the macro expands to nothing observable.
+///
+/// ```rust
+/// use davenport::{debug_assert_workspace_free, define_thread_local_workspace};
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// debug_assert_workspace_free!(WORKSPACE);
+/// ```
+#[macro_export]
+macro_rules! debug_assert_workspace_free {
+    ($variable_name:ident) => {
+        if cfg!(debug_assertions) {
+            $crate::WorkspaceKey::with_cell(&&$variable_name, |workspace| {
+                assert!(
+                    workspace.try_borrow_mut().is_ok(),
+                    "Workspace {} is currently in use on this thread.",
+                    stringify!($variable_name)
+                );
+            });
+        }
+    };
+}
+
+/// Runs the provided closure with a single field of a thread-local workspace struct.
+///
+/// `with_workspace_field!(WORKSPACE, MyWorkspace, field, |buffer| body)` is shorthand for
+/// calling [`with_thread_local_workspace`] with a closure taking `&mut MyWorkspace`, which
+/// binds `buffer` to `&mut workspace.field` before evaluating `body`.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_workspace_field};
+///
+/// #[derive(Default)]
+/// pub struct MyWorkspace {
+///     index_buffer: Vec<usize>,
+/// }
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// fn median_floor(indices: &[usize]) -> Option<usize> {
+///     with_workspace_field!(WORKSPACE, MyWorkspace, index_buffer, |buffer| {
+///         buffer.clear();
+///         buffer.extend_from_slice(indices);
+///         buffer.sort_unstable();
+///         buffer.get(indices.len() / 2).copied()
+///     })
+/// }
+///
+/// assert_eq!(median_floor(&[3, 1, 2]), Some(2));
+/// ```
+///
+/// ## Panics
+///
+/// Panics if used recursively with the same workspace variable.
+#[macro_export]
+macro_rules! with_workspace_field {
+    ($variable_name:ident, $workspace_type:ty, $field:ident, |$binding:ident| $body:expr) => {
+        $crate::with_thread_local_workspace(&$variable_name, |workspace: &mut $workspace_type| {
+            let $binding = &mut workspace.$field;
+            $body
+        })
+    };
+}
+
 /// Helper macro for easily defining thread-local workspaces.
 ///
 /// See the [crate-level documentation](`crate`) for usage instructions.
+///
+/// With the `wasm-single-thread` feature, on WebAssembly targets without the `atomics` target
+/// feature, the macro instead defines a `GlobalWorkspace` in a plain `static`, which avoids
+/// the overhead of thread-local storage. Such targets can not spawn threads, which upholds the
+/// single-thread contract of `GlobalWorkspace`. The variable is used in the same way in both
+/// cases, since both implement [`WorkspaceKey`].
+#[cfg(not(all(
+    feature = "wasm-single-thread",
+    target_family = "wasm",
+    not(target_feature = "atomics")
+)))]
 #[macro_export]
 macro_rules! define_thread_local_workspace {
     ($variable_name:ident) => {
         thread_local! {
             static $variable_name: std::cell::RefCell<$crate::Workspace>
-                = std::cell::RefCell::new($crate::Workspace::default());
+                = const { std::cell::RefCell::new($crate::Workspace::new()) };
         }
     };
 }
+
+/// Helper macro for easily defining thread-local workspaces.
+///
+/// See the [crate-level documentation](`crate`) for usage instructions.
+#[cfg(all(
+    feature = "wasm-single-thread",
+    target_family = "wasm",
+    not(target_feature = "atomics")
+))]
+#[macro_export]
+macro_rules! define_thread_local_workspace {
+    ($variable_name:ident) => {
+        // SAFETY: WebAssembly without the `atomics` target feature has no threads, so the
+        // workspace is only ever accessed from a single thread
+        static $variable_name: $crate::GlobalWorkspace = unsafe { $crate::GlobalWorkspace::new() };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "multiple entries of the same type")]
+    fn validate_detects_duplicate_types() {
+        let mut ws = Workspace::new();
+        ws.push_entry(Entry::new(1u32));
+        ws.push_entry(Entry::new(2u32));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "does not wrap a value of the recorded type")]
+    fn validate_detects_keyed_entry_in_unkeyed_slot() {
+        let mut ws = Workspace::new();
+        let mut entry = Entry::new(1u32);
+        entry.keyed_value = Some((TypeId::of::<u32>(), |any| any));
+        ws.push_entry(entry);
+    }
+
+    #[test]
+    #[cfg(all(feature = "debug-names", debug_assertions))]
+    #[should_panic(expected = "TypeId collision")]
+    fn get_or_default_detects_type_name_mismatch() {
+        let mut ws = Workspace::new();
+        ws.push_entry(Entry::new(1u32)).type_name = "not_u32";
+        let _ = ws.get_or_default::<u32>();
+    }
+}
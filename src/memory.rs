@@ -0,0 +1,175 @@
+use crate::Workspace;
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, VecDeque};
+use std::mem::size_of;
+
+/// Types that can report the number of heap bytes they hold.
+///
+/// Memory usage is only accounted for types registered with
+/// [`Workspace::register_memory_usage`].
+pub trait WorkspaceMemoryUsage {
+    /// Returns the (approximate) number of bytes of heap memory held by the object.
+    fn memory_usage(&self) -> usize;
+}
+
+impl<T> WorkspaceMemoryUsage for Vec<T> {
+    fn memory_usage(&self) -> usize {
+        self.capacity() * size_of::<T>()
+    }
+}
+
+impl<T> WorkspaceMemoryUsage for VecDeque<T> {
+    fn memory_usage(&self) -> usize {
+        self.capacity() * size_of::<T>()
+    }
+}
+
+impl WorkspaceMemoryUsage for String {
+    fn memory_usage(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<K, V, S> WorkspaceMemoryUsage for HashMap<K, V, S> {
+    fn memory_usage(&self) -> usize {
+        self.capacity() * size_of::<(K, V)>()
+    }
+}
+
+/// An event reported to the observer set with [`Workspace::set_alloc_observer`].
+///
+/// The reported sizes are obtained through [`WorkspaceMemoryUsage`], and are zero for types
+/// that have not been registered with [`Workspace::register_memory_usage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocEvent {
+    /// An object was inserted into the workspace.
+    Created { type_id: TypeId, bytes: usize },
+    /// An object was removed from the workspace, e.g. when clearing, evicting, removing with
+    /// [`Workspace::invalidate`] or dropping the workspace itself. Objects moved out of the
+    /// workspace, e.g. with [`Workspace::take_or_default`] or [`Workspace::extract_if`], are
+    /// reported in the same way, and replacing an object, e.g. with [`Workspace::insert_dyn`],
+    /// reports the removal of the old object followed by the creation of the new one.
+    Dropped { type_id: TypeId, bytes: usize },
+}
+
+pub(crate) type MemoryUsageFn = fn(&dyn Any) -> usize;
+
+fn memory_usage_of<W: 'static + WorkspaceMemoryUsage>(any: &dyn Any) -> usize {
+    any.downcast_ref::<W>()
+        .map_or(0, WorkspaceMemoryUsage::memory_usage)
+}
+
+impl Workspace {
+    /// Registers `W` for memory usage accounting.
+    ///
+    /// Objects of types that have not been registered are assumed to use no memory.
+    pub fn register_memory_usage<W: 'static + WorkspaceMemoryUsage>(&mut self) {
+        let type_id = TypeId::of::<W>();
+        if !self.memory_usage_fns.iter().any(|(id, _)| *id == type_id) {
+            self.memory_usage_fns.push((type_id, memory_usage_of::<W>));
+        }
+    }
+
+    /// Returns the total memory usage of all objects of registered types.
+    pub fn memory_usage(&self) -> usize {
+        (0..self.workspaces.len())
+            .map(|idx| self.memory_usage_at(idx))
+            .sum()
+    }
+
+    /// Sets a budget for the total memory usage of the workspace, in bytes.
+    ///
+    /// Whenever an object is accessed through one of the `get_or_*` methods and the total
+    /// memory usage, as reported through [`WorkspaceMemoryUsage`], exceeds the budget, the least
+    /// recently used objects are evicted until the workspace is within budget again. The object
+    /// being accessed is never evicted, so a single object larger than the budget is retained.
+    ///
+    /// Recency follows the internal order of the workspace, in which accessed objects are moved
+    /// to the back, see [`Workspace::peek_lru_type_id`]. Only objects of registered types are
+    /// evicted.
+    ///
+    /// Note that the budget is enforced at the time of access, so memory allocated by
+    /// modifying the returned reference is only accounted for on the next access.
+    pub fn set_memory_budget(&mut self, bytes: Option<usize>) {
+        self.memory_budget = bytes;
+    }
+
+    /// Sets an observer that is notified whenever an object is created in or dropped by the
+    /// workspace, along with its memory usage at that time.
+    ///
+    /// This provides a seam for attributing memory to workspaces in external accounting, e.g.
+    /// with a counting global allocator. Any previously set observer is replaced.
+    pub fn set_alloc_observer(&mut self, observer: Box<dyn FnMut(AllocEvent)>) {
+        self.alloc_observer = Some(observer);
+    }
+
+    pub(crate) fn notify_created(&mut self, idx: usize) {
+        if self.alloc_observer.is_none() {
+            return;
+        }
+        let event = AllocEvent::Created {
+            type_id: self.workspaces[idx].value_type_id(),
+            bytes: self.memory_usage_at(idx),
+        };
+        if let Some(observer) = &mut self.alloc_observer {
+            observer(event);
+        }
+    }
+
+    pub(crate) fn notify_dropped(&mut self, idx: usize) {
+        if self.alloc_observer.is_none() {
+            return;
+        }
+        let event = AllocEvent::Dropped {
+            type_id: self.workspaces[idx].value_type_id(),
+            bytes: self.memory_usage_at(idx),
+        };
+        if let Some(observer) = &mut self.alloc_observer {
+            observer(event);
+        }
+    }
+
+    /// Removes and drops the object at the given index.
+    pub(crate) fn discard_at(&mut self, idx: usize) {
+        self.notify_dropped(idx);
+        self.workspaces.remove(idx).discard();
+    }
+
+    /// Evicts least recently used objects until the workspace is within its memory budget.
+    ///
+    /// The most recently used object is never evicted.
+    pub(crate) fn enforce_memory_budget(&mut self) {
+        if let Some(last) = self.workspaces.last() {
+            let last = last.value_type_id();
+            self.enforce_memory_budget_sparing(&[last]);
+        }
+    }
+
+    /// Same as `enforce_memory_budget`, but never evicts objects of the given types.
+    pub(crate) fn enforce_memory_budget_sparing(&mut self, spared: &[TypeId]) {
+        let budget = match self.memory_budget {
+            Some(budget) => budget,
+            None => return,
+        };
+        let mut usage = self.memory_usage();
+        let mut idx = 0;
+        while usage > budget && idx < self.workspaces.len() {
+            let entry_usage = self.memory_usage_at(idx);
+            if entry_usage > 0 && !spared.contains(&self.workspaces[idx].value_type_id()) {
+                self.discard_at(idx);
+                usage -= entry_usage;
+            } else {
+                idx += 1;
+            }
+        }
+    }
+
+    pub(crate) fn memory_usage_at(&self, idx: usize) -> usize {
+        let entry = &self.workspaces[idx];
+        let type_id = entry.value_type_id();
+        self.memory_usage_fns
+            .iter()
+            .find(|(id, _)| *id == type_id)
+            .map_or(0, |(_, usage)| usage(&*entry.value))
+    }
+}
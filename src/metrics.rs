@@ -0,0 +1,124 @@
+use crate::Workspace;
+use std::any::TypeId;
+
+/// Access statistics for a single type stored in a [`Workspace`].
+///
+/// See [`Workspace::export_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TypeStats {
+    /// The number of accesses through [`Workspace::get_or_insert_with`] and the methods built
+    /// on it that found an existing object.
+    pub hits: u64,
+    /// The number of such accesses that found no existing object.
+    pub misses: u64,
+    /// The number of objects of the type that were inserted into the workspace, by any method.
+    pub creations: u64,
+    /// The largest memory usage observed for an object of the type, in bytes.
+    ///
+    /// This is `None` unless the type has been registered with
+    /// [`Workspace::register_memory_usage`]. Memory usage is sampled on each access, so growth
+    /// after the last access is not accounted for.
+    pub peak_bytes: Option<usize>,
+}
+
+impl Workspace {
+    /// Returns access statistics for every type that has been accessed or inserted.
+    ///
+    /// Statistics are kept per type rather than per object, so they survive the removal of
+    /// objects, e.g. through [`Workspace::clear`] or eviction. The order of the returned types
+    /// is unspecified.
+    pub fn export_stats(&self) -> Vec<(TypeId, TypeStats)> {
+        self.stats.clone()
+    }
+
+    /// Sets the number of consecutive accesses alternating between two types above which a
+    /// warning is logged.
+    ///
+    /// Every access moves the accessed object to the most-recently-used position, which is
+    /// ideal when the same type is accessed many times in a row. When accesses strictly
+    /// alternate between two types, every access instead requires a search and a move. Past
+    /// the threshold, a warning is emitted through the `log` crate, at most once per workspace,
+    /// suggesting to obtain both objects at once with [`Workspace::get_pair_mut`], or to avoid
+    /// the lookup altogether with a [`WorkspaceHandle`](crate::WorkspaceHandle). The default
+    /// threshold is 1000.
+    pub fn set_swap_thrash_threshold(&mut self, threshold: usize) {
+        self.thrash_detector.threshold = threshold;
+    }
+
+    pub(crate) fn record_access_stats(&mut self, type_id: TypeId, hit: bool) {
+        self.thrash_detector.record(type_id);
+        let stats = self.stats_mut(type_id);
+        if hit {
+            stats.hits += 1;
+        } else {
+            stats.misses += 1;
+        }
+        let usage = self
+            .memory_usage_fns
+            .iter()
+            .any(|(id, _)| *id == type_id)
+            .then(|| self.memory_usage_at(self.workspaces.len() - 1));
+        if let Some(usage) = usage {
+            let stats = self.stats_mut(type_id);
+            stats.peak_bytes = Some(stats.peak_bytes.unwrap_or(0).max(usage));
+        }
+    }
+
+    pub(crate) fn record_creation_stats(&mut self, type_id: TypeId) {
+        self.stats_mut(type_id).creations += 1;
+    }
+
+    fn stats_mut(&mut self, type_id: TypeId) -> &mut TypeStats {
+        let idx = match self.stats.iter().position(|(id, _)| *id == type_id) {
+            Some(idx) => idx,
+            None => {
+                self.stats.push((type_id, TypeStats::default()));
+                self.stats.len() - 1
+            }
+        };
+        &mut self.stats[idx].1
+    }
+}
+
+/// Detects accesses that strictly alternate between two types.
+#[derive(Debug)]
+pub(crate) struct ThrashDetector {
+    // The types of the two most recent accesses, the most recent one last
+    recent: [Option<TypeId>; 2],
+    // The number of consecutive accesses that alternated between the two most recent types
+    alternations: usize,
+    threshold: usize,
+    warned: bool,
+}
+
+impl ThrashDetector {
+    pub(crate) const fn new() -> Self {
+        Self {
+            recent: [None, None],
+            alternations: 0,
+            threshold: 1000,
+            warned: false,
+        }
+    }
+
+    fn record(&mut self, type_id: TypeId) {
+        let [previous, last] = self.recent;
+        if previous == Some(type_id) && last != Some(type_id) {
+            self.alternations += 1;
+        } else {
+            self.alternations = 0;
+        }
+        self.recent = [last, Some(type_id)];
+
+        if !self.warned && self.alternations > self.threshold {
+            self.warned = true;
+            log::warn!(
+                "Workspace accesses alternated between two types {} times in a row. \
+                 Each such access requires a search and a swap, since the workspace is \
+                 optimized for repeated access to the same type. Consider obtaining both \
+                 objects at once with `Workspace::get_pair_mut`, or using a `WorkspaceHandle`.",
+                self.alternations
+            );
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{with_borrowed_workspace, WorkspaceKey};
+
+/// The byte used by the [`Poison`] implementation for `Vec`.
+const POISON_BYTE: u8 = 0xDE;
+
+/// Objects that can overwrite their contents with an easily recognizable sentinel.
+///
+/// Reusing a buffer without first clearing it is a common source of bugs, as the stale
+/// contents of a previous call often look plausible. With the `debug-poison` feature,
+/// [`with_thread_local_workspace_poisoned`] poisons the object after each use, so that
+/// forgetting to clear it yields obviously wrong data instead.
+pub trait Poison {
+    /// Overwrites the contents of the object with a sentinel value.
+    ///
+    /// The object need not be in a meaningful state afterwards, but must remain valid to
+    /// clear and reuse.
+    fn poison(&mut self);
+}
+
+impl<T: From<u8>> Poison for Vec<T> {
+    /// Overwrites every element with `0xDE`, without changing the length.
+    fn poison(&mut self) {
+        for x in self.iter_mut() {
+            *x = T::from(POISON_BYTE);
+        }
+    }
+}
+
+/// Same as [`with_thread_local_workspace`](crate::with_thread_local_workspace), but with the
+/// `debug-poison` feature, poisons the object when the closure returns.
+///
+/// Without the feature, the object is left as is, so that this is a drop-in replacement for
+/// [`with_thread_local_workspace`](crate::with_thread_local_workspace) whose checking can be
+/// enabled for debug builds only.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_thread_local_workspace_poisoned};
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// let sum = with_thread_local_workspace_poisoned(&WORKSPACE, |buffer: &mut Vec<u32>| {
+///     buffer.clear();
+///     buffer.extend([1, 2, 3]);
+///     buffer.iter().sum::<u32>()
+/// });
+/// assert_eq!(sum, 6);
+/// ```
+///
+/// ## Panics
+///
+/// Panics if used recursively with the same workspace variable.
+pub fn with_thread_local_workspace_poisoned<W, T>(
+    workspace: impl WorkspaceKey,
+    f: impl FnOnce(&mut W) -> T,
+) -> T
+where
+    W: 'static + Default + Poison,
+{
+    with_borrowed_workspace(workspace, |type_erased_workspace| {
+        let workspace = type_erased_workspace.get_or_default::<W>();
+        let result = f(workspace);
+        #[cfg(feature = "debug-poison")]
+        workspace.poison();
+        result
+    })
+}
@@ -0,0 +1,181 @@
+use crate::{CapacityReport, Workspace};
+use std::any::Any;
+use std::collections::VecDeque;
+
+/// Determines what happens to the memory held by a workspace object after it has been used.
+///
+/// A policy is attached to an object with [`Workspace::get_or_default_with_policy`], and is
+/// applied after each use through
+/// [`with_thread_local_workspace`](crate::with_thread_local_workspace) or explicitly with
+/// [`Workspace::apply_memory_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MemoryPolicy {
+    /// The object is never shrunk, so that its capacity only ever grows.
+    ///
+    /// This is the default, and is appropriate for buffers used in hot loops.
+    #[default]
+    GrowOnly,
+    /// After each use, the capacity of the object is reduced to `keep_ratio` times its length
+    /// if it exceeds this limit.
+    ///
+    /// For example, with a `keep_ratio` of `2.0`, an object may retain at most twice the
+    /// capacity that it needed in its last use. Ratios smaller than `1.0` behave like `1.0`.
+    ShrinkAfterUse { keep_ratio: f64 },
+    /// After each use, if the capacity of the object exceeds `max_capacity`, the object is
+    /// truncated to at most `max_capacity` elements and its capacity reduced accordingly.
+    ///
+    /// Unlike [`MemoryPolicy::ShrinkAfterUse`], this bounds the retained memory regardless of
+    /// how many elements were in use, at the cost of discarding elements beyond the limit.
+    RetainAtMost { max_capacity: usize },
+}
+
+/// Types whose capacity can be reduced, for use with [`MemoryPolicy`].
+pub trait Shrinkable: CapacityReport {
+    /// The number of elements currently in use.
+    fn used_len(&self) -> usize;
+
+    /// Reduces the capacity to at least `min_capacity`, or the used length, whichever is larger.
+    fn shrink_to(&mut self, min_capacity: usize);
+
+    /// Shortens the object to at most `len` elements.
+    fn truncate(&mut self, len: usize);
+}
+
+impl<T> Shrinkable for Vec<T> {
+    fn used_len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn shrink_to(&mut self, min_capacity: usize) {
+        Vec::shrink_to(self, min_capacity)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        Vec::truncate(self, len)
+    }
+}
+
+impl<T> Shrinkable for VecDeque<T> {
+    fn used_len(&self) -> usize {
+        VecDeque::len(self)
+    }
+
+    fn shrink_to(&mut self, min_capacity: usize) {
+        VecDeque::shrink_to(self, min_capacity)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        VecDeque::truncate(self, len)
+    }
+}
+
+impl Shrinkable for String {
+    fn used_len(&self) -> usize {
+        String::len(self)
+    }
+
+    fn shrink_to(&mut self, min_capacity: usize) {
+        String::shrink_to(self, min_capacity)
+    }
+
+    fn truncate(&mut self, mut len: usize) {
+        if len < self.len() {
+            // Truncate to the nearest character boundary that does not exceed `len`
+            while !self.is_char_boundary(len) {
+                len -= 1;
+            }
+            String::truncate(self, len)
+        }
+    }
+}
+
+pub(crate) type ApplyPolicyFn = fn(&mut dyn Any, MemoryPolicy);
+
+fn apply_policy<W: 'static + Shrinkable>(any: &mut dyn Any, policy: MemoryPolicy) {
+    let w = any
+        .downcast_mut::<W>()
+        .expect("Internal error: Policy is only applied to matching type");
+    match policy {
+        MemoryPolicy::GrowOnly => {}
+        MemoryPolicy::ShrinkAfterUse { keep_ratio } => {
+            let max_capacity = (w.used_len() as f64 * keep_ratio.max(1.0)).ceil() as usize;
+            if w.capacity() > max_capacity {
+                w.shrink_to(max_capacity);
+            }
+        }
+        MemoryPolicy::RetainAtMost { max_capacity } => {
+            if w.capacity() > max_capacity {
+                w.truncate(max_capacity);
+                w.shrink_to(max_capacity);
+            }
+        }
+    }
+}
+
+impl Workspace {
+    /// Same as [`Workspace::get_or_default`], but attaches the given memory policy to the
+    /// object if it is newly inserted.
+    ///
+    /// The policy of an object that is already present is left unchanged.
+    pub fn get_or_default_with_policy<W>(&mut self, policy: MemoryPolicy) -> &mut W
+    where
+        W: 'static + Default + Shrinkable,
+    {
+        let existing = self.find_index_of::<W>().is_some();
+        let entry = self.get_or_insert_entry_with(W::default);
+        if !existing {
+            entry.memory_policy = Some((policy, apply_policy::<W>));
+        }
+        entry
+            .downcast_mut()
+            .expect("Internal error: Downcasting can by definition not fail")
+    }
+
+    /// Returns a slice of length `len` backed by a reused `Vec<T>`, whose retained capacity is
+    /// capped at `max_retained` elements after use.
+    ///
+    /// This is the same as [`Workspace::get_slice_or_default`], except that the policy
+    /// [`MemoryPolicy::RetainAtMost`] is attached to the `Vec<T>`, replacing any previously
+    /// attached policy. A single call with a very large `len` therefore does not leave a huge
+    /// buffer behind. The cap is applied after use through
+    /// [`with_thread_local_capped_slice`](crate::with_thread_local_capped_slice), or explicitly
+    /// with [`Workspace::apply_memory_policy`].
+    pub fn get_capped_slice_or_default<T>(&mut self, len: usize, max_retained: usize) -> &mut [T]
+    where
+        T: 'static + Default + Clone,
+    {
+        let entry = self.get_or_insert_entry_with(Vec::<T>::new);
+        let policy = MemoryPolicy::RetainAtMost {
+            max_capacity: max_retained,
+        };
+        entry.memory_policy = Some((policy, apply_policy::<Vec<T>>));
+        let buffer: &mut Vec<T> = entry
+            .downcast_mut()
+            .expect("Internal error: Downcasting can by definition not fail");
+        buffer.resize(len, T::default());
+        buffer
+    }
+
+    /// Applies the memory policy attached to the object of type `W`, if any.
+    pub fn apply_memory_policy<W: 'static>(&mut self) {
+        if let Some(idx) = self.find_index_of::<W>() {
+            self.workspaces[idx].apply_memory_policy();
+        }
+    }
+
+    /// Applies the memory policies attached to all objects in the workspace.
+    ///
+    /// Objects without an attached policy are left as is.
+    pub fn shrink_all(&mut self) {
+        for entry in &mut self.workspaces {
+            entry.apply_memory_policy();
+        }
+    }
+
+    /// Applies the memory policy of the most recently used object, if any.
+    pub(crate) fn apply_memory_policy_of_last(&mut self) {
+        if let Some(entry) = self.workspaces.last_mut() {
+            entry.apply_memory_policy();
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use crate::Workspace;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+/// A pool of reusable objects, keyed by type.
+///
+/// Whereas a [`Workspace`] holds at most one object of each type, a `WorkspacePool` can hand out
+/// several objects of the same type at the same time. This is useful for recursive algorithms,
+/// in which each level of recursion needs its own buffer and recursive use of a single
+/// workspace would otherwise panic.
+///
+/// Objects are checked out with [`WorkspacePool::checkout`], and are returned to the pool when
+/// the resulting [`PooledItem`] is dropped. Returned objects are not reset, so their previous
+/// contents are visible on the next checkout.
+///
+/// ```rust
+/// use davenport::WorkspacePool;
+///
+/// fn depth_sum(pool: &WorkspacePool, depth: usize) -> usize {
+///     let mut buffer = pool.checkout::<Vec<usize>>();
+///     buffer.clear();
+///     buffer.push(depth);
+///     if depth > 0 {
+///         buffer.push(depth_sum(pool, depth - 1));
+///     }
+///     buffer.iter().sum()
+/// }
+///
+/// let pool = WorkspacePool::default();
+/// assert_eq!(depth_sum(&pool, 3), 6);
+/// assert_eq!(pool.available::<Vec<usize>>(), 4);
+/// ```
+#[derive(Debug, Default)]
+pub struct WorkspacePool {
+    // Holds a `FreeList<W>` for each pooled type `W`
+    free: RefCell<Workspace>,
+}
+
+struct FreeList<W>(Vec<W>);
+
+impl<W> Default for FreeList<W> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl WorkspacePool {
+    /// Creates a new, empty pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks out an object of type `W` from the pool.
+    ///
+    /// If no object of type `W` is available, a default-constructed object is created.
+    pub fn checkout<W: 'static + Default>(&self) -> PooledItem<'_, W> {
+        let available = self
+            .free
+            .borrow_mut()
+            .get_or_default::<FreeList<W>>()
+            .0
+            .pop();
+        PooledItem {
+            item: Some(available.unwrap_or_default()),
+            pool: self,
+        }
+    }
+
+    /// Returns the number of objects of type `W` that are currently available in the pool.
+    pub fn available<W: 'static>(&self) -> usize {
+        self.free
+            .borrow()
+            .try_get::<FreeList<W>>()
+            .map_or(0, |free| free.0.len())
+    }
+
+    fn give_back<W: 'static>(&self, item: W) {
+        self.free
+            .borrow_mut()
+            .get_or_default::<FreeList<W>>()
+            .0
+            .push(item);
+    }
+}
+
+/// An object checked out from a [`WorkspacePool`].
+///
+/// The object is returned to the pool when the `PooledItem` is dropped.
+pub struct PooledItem<'a, W: 'static> {
+    // Always Some until dropped
+    item: Option<W>,
+    pool: &'a WorkspacePool,
+}
+
+impl<'a, W: 'static> PooledItem<'a, W> {
+    /// Removes the object from the pool entirely, so that it is not returned on drop.
+    pub fn into_inner(mut self) -> W {
+        self.item.take().unwrap()
+    }
+}
+
+impl<'a, W: 'static> Deref for PooledItem<'a, W> {
+    type Target = W;
+
+    fn deref(&self) -> &W {
+        self.item.as_ref().unwrap()
+    }
+}
+
+impl<'a, W: 'static> DerefMut for PooledItem<'a, W> {
+    fn deref_mut(&mut self) -> &mut W {
+        self.item.as_mut().unwrap()
+    }
+}
+
+impl<'a, W: 'static> Drop for PooledItem<'a, W> {
+    fn drop(&mut self) {
+        if let Some(item) = self.item.take() {
+            self.pool.give_back(item);
+        }
+    }
+}
+
+impl<'a, W: 'static + fmt::Debug> fmt::Debug for PooledItem<'a, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PooledItem").field(&self.item).finish()
+    }
+}
+
+/// Pooled temporary buffers, which are cleared and returned for reuse when they go out of
+/// scope.
+///
+/// This builds on [`WorkspacePool`], but guarantees that every rented buffer starts out empty.
+/// It can itself be stored in a [`Workspace`], providing reusable temporaries at every level of
+/// a recursion within a single workspace borrow.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_thread_local_workspace};
+/// use davenport::ScratchAllocator;
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// fn count_leaves(scratch: &ScratchAllocator, depth: usize) -> usize {
+///     let mut children = scratch.rent::<Vec<usize>>();
+///     if depth == 0 {
+///         return 1;
+///     }
+///     children.extend([depth - 1, depth - 1]);
+///     children.iter().map(|&child| count_leaves(scratch, child)).sum()
+/// }
+///
+/// let leaves = with_thread_local_workspace(&WORKSPACE, |scratch: &mut ScratchAllocator| {
+///     count_leaves(scratch, 3)
+/// });
+/// assert_eq!(leaves, 8);
+/// ```
+#[derive(Debug, Default)]
+pub struct ScratchAllocator {
+    pool: WorkspacePool,
+}
+
+impl ScratchAllocator {
+    /// Creates a new, empty allocator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rents an empty buffer of type `W`, reusing a previously returned buffer if available.
+    pub fn rent<W: 'static + Default + Clear>(&self) -> Rented<'_, W> {
+        Rented {
+            item: self.pool.checkout(),
+        }
+    }
+
+    /// Returns the number of buffers of type `W` that are currently available for reuse.
+    pub fn available<W: 'static>(&self) -> usize {
+        self.pool.available::<W>()
+    }
+}
+
+/// A buffer rented from a [`ScratchAllocator`].
+///
+/// The buffer is cleared and returned to the allocator when the `Rented` is dropped.
+#[derive(Debug)]
+pub struct Rented<'a, W: 'static + Clear> {
+    item: PooledItem<'a, W>,
+}
+
+impl<'a, W: 'static + Clear> Deref for Rented<'a, W> {
+    type Target = W;
+
+    fn deref(&self) -> &W {
+        &self.item
+    }
+}
+
+impl<'a, W: 'static + Clear> DerefMut for Rented<'a, W> {
+    fn deref_mut(&mut self) -> &mut W {
+        &mut self.item
+    }
+}
+
+impl<'a, W: 'static + Clear> Drop for Rented<'a, W> {
+    fn drop(&mut self) {
+        self.item.clear();
+    }
+}
+
+/// Buffers that can be emptied while retaining their allocation, for use with
+/// [`ScratchAllocator`].
+pub trait Clear {
+    /// Removes all contents, retaining allocated capacity.
+    fn clear(&mut self);
+}
+
+impl<T> Clear for Vec<T> {
+    fn clear(&mut self) {
+        Vec::clear(self)
+    }
+}
+
+impl<T> Clear for VecDeque<T> {
+    fn clear(&mut self) {
+        VecDeque::clear(self)
+    }
+}
+
+impl Clear for String {
+    fn clear(&mut self) {
+        String::clear(self)
+    }
+}
+
+impl<K, V, S> Clear for HashMap<K, V, S> {
+    fn clear(&mut self) {
+        HashMap::clear(self)
+    }
+}
+
+impl<T, S> Clear for HashSet<T, S> {
+    fn clear(&mut self) {
+        HashSet::clear(self)
+    }
+}
+
+/// A single buffer whose allocation is shared between mutually exclusive phases.
+///
+/// Storing two large `Vec<T>` objects in a [`Workspace`] for phases of a computation that never
+/// run at the same time keeps two large allocations alive. A `SharedCapacityPool` instead holds
+/// a single `Vec<T>`, which each phase checks out with [`SharedCapacityPool::checkout`]. Phases
+/// are identified by marker types, and only one phase may hold the buffer at any time. The
+/// buffer is cleared on every checkout, so no data is passed between phases, but its capacity
+/// is retained.
+///
+/// ```rust
+/// use davenport::SharedCapacityPool;
+///
+/// struct Assembly;
+/// struct Solve;
+///
+/// let pool = SharedCapacityPool::<f64>::new();
+/// pool.checkout::<Assembly>().resize(1000, 1.0);
+/// let solve_buffer = pool.checkout::<Solve>();
+/// assert!(solve_buffer.is_empty());
+/// assert!(solve_buffer.capacity() >= 1000);
+/// ```
+pub struct SharedCapacityPool<T> {
+    buffer: RefCell<Vec<T>>,
+    checked_out_by: Cell<Option<&'static str>>,
+}
+
+impl<T> SharedCapacityPool<T> {
+    /// Creates a new pool with an empty buffer. Does not allocate.
+    pub const fn new() -> Self {
+        Self {
+            buffer: RefCell::new(Vec::new()),
+            checked_out_by: Cell::new(None),
+        }
+    }
+
+    /// Checks out the buffer for the phase identified by the marker type `Phase`.
+    ///
+    /// The returned buffer is empty, but retains the capacity from previous checkouts. It is
+    /// returned to the pool when the [`PhaseBuffer`] is dropped.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the buffer is currently checked out by any phase.
+    pub fn checkout<Phase: 'static>(&self) -> PhaseBuffer<'_, T> {
+        if let Some(holder) = self.checked_out_by.get() {
+            panic!(
+                "Can not check out shared buffer for phase {}, \
+                 since it is already checked out by phase {}.",
+                std::any::type_name::<Phase>(),
+                holder
+            );
+        }
+        let mut buffer = self.buffer.take();
+        buffer.clear();
+        self.checked_out_by
+            .set(Some(std::any::type_name::<Phase>()));
+        PhaseBuffer { buffer, pool: self }
+    }
+
+    /// The capacity of the shared buffer, or zero if it is currently checked out.
+    pub fn capacity(&self) -> usize {
+        self.buffer.borrow().capacity()
+    }
+}
+
+impl<T> Default for SharedCapacityPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for SharedCapacityPool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedCapacityPool")
+            .field("capacity", &self.capacity())
+            .field("checked_out_by", &self.checked_out_by.get())
+            .finish()
+    }
+}
+
+/// The buffer of a [`SharedCapacityPool`], checked out for a single phase.
+///
+/// The buffer is returned to the pool when the `PhaseBuffer` is dropped.
+pub struct PhaseBuffer<'a, T> {
+    buffer: Vec<T>,
+    pool: &'a SharedCapacityPool<T>,
+}
+
+impl<'a, T> Deref for PhaseBuffer<'a, T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Vec<T> {
+        &self.buffer
+    }
+}
+
+impl<'a, T> DerefMut for PhaseBuffer<'a, T> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.buffer
+    }
+}
+
+impl<'a, T> Drop for PhaseBuffer<'a, T> {
+    fn drop(&mut self) {
+        self.pool.buffer.replace(std::mem::take(&mut self.buffer));
+        self.pool.checked_out_by.set(None);
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for PhaseBuffer<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PhaseBuffer").field(&self.buffer).finish()
+    }
+}
@@ -0,0 +1,32 @@
+use crate::{with_borrowed_workspace, Workspace, WorkspaceKey};
+
+/// Clears the given thread-local workspace on every thread of a rayon thread pool.
+///
+/// Each worker thread of a pool holds its own instance of a thread-local workspace, which
+/// otherwise retains its memory for as long as the thread lives. Calling this between large
+/// jobs releases that memory on all workers at once. The call blocks until every worker has
+/// cleared its workspace, in the manner of [`rayon::ThreadPool::broadcast`].
+///
+/// ```rust
+/// use davenport::{clear_workspace_on_all_threads, define_thread_local_workspace};
+/// use davenport::with_thread_local_workspace;
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+/// pool.broadcast(|_| {
+///     with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u8>| buffer.resize(1024, 0));
+/// });
+/// clear_workspace_on_all_threads(&pool, &WORKSPACE);
+/// ```
+///
+/// ## Panics
+///
+/// Panics if the workspace is in use on a worker thread when the worker is reached, e.g.
+/// because a job on that worker waits for other jobs while using the workspace.
+pub fn clear_workspace_on_all_threads(
+    pool: &rayon::ThreadPool,
+    workspace: impl WorkspaceKey + Sync,
+) {
+    pool.broadcast(|_| with_borrowed_workspace(&workspace, Workspace::clear));
+}
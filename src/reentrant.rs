@@ -0,0 +1,147 @@
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::thread::LocalKey;
+
+/// A workspace in which every type is stored in its own cell, permitting nested access to
+/// distinct types.
+///
+/// A thread-local [`Workspace`](crate::Workspace) is guarded by a single
+/// [`RefCell`], so any nested use of the same workspace variable panics, even when the nested
+/// use accesses a different type. `ReentrantWorkspace` instead stores each object in a separate
+/// `RefCell`, and only borrows the object being accessed for the duration of
+/// [`ReentrantWorkspace::with`].
+///
+/// The precise guarantee is as follows. While the closure passed to `with::<W>` runs, the
+/// object of type `W` is mutably borrowed, and nothing else is. Within the closure:
+///
+/// - accessing any type other than `W`, including types that are inserted for the first time,
+///   succeeds;
+/// - accessing `W` again panics, since this would create two mutable references to the same
+///   object.
+///
+/// This holds regardless of nesting depth, so a recursive algorithm is fine as long as each
+/// level accesses a type that is not in use by any enclosing level.
+///
+/// Compared to [`Workspace`](crate::Workspace), each access requires an additional reference
+/// count increment and decrement. The [`Workspace`](crate::Workspace) API beyond plain access
+/// by type is not available.
+///
+/// ```rust
+/// use davenport::{define_thread_local_reentrant_workspace, with_thread_local_reentrant_workspace};
+///
+/// define_thread_local_reentrant_workspace!(WORKSPACE);
+///
+/// let total = with_thread_local_reentrant_workspace(&WORKSPACE, |outer: &mut Vec<u32>| {
+///     outer.push(1);
+///     // Nested access to a different type does not panic
+///     with_thread_local_reentrant_workspace(&WORKSPACE, |inner: &mut Vec<u64>| {
+///         inner.push(2);
+///         inner.len() + outer.len()
+///     })
+/// });
+/// assert_eq!(total, 2);
+/// ```
+#[derive(Default)]
+pub struct ReentrantWorkspace {
+    // Each value is an `Rc<RefCell<W>>` for the type `W` identified by the `TypeId`
+    cells: RefCell<Vec<(TypeId, Rc<dyn Any>)>>,
+}
+
+impl ReentrantWorkspace {
+    /// Creates a new, empty workspace.
+    pub const fn new() -> Self {
+        Self {
+            cells: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Runs the provided closure with the object of type `W`, inserting a default-constructed
+    /// object if it is not present.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the object of type `W` is already in use, i.e. if called from within
+    /// another call to `with::<W>` on the same workspace.
+    pub fn with<W: 'static + Default, T>(&self, f: impl FnOnce(&mut W) -> T) -> T {
+        let cell = self.cell::<W>();
+        let mut w = cell.try_borrow_mut().unwrap_or_else(|_| {
+            panic!(
+                "Can not recursively access the object of type {} in a reentrant workspace.",
+                std::any::type_name::<W>()
+            )
+        });
+        f(&mut w)
+    }
+
+    /// Returns the number of objects stored in the workspace.
+    pub fn len(&self) -> usize {
+        self.cells.borrow().len()
+    }
+
+    /// Returns `true` if the workspace holds no objects.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn cell<W: 'static + Default>(&self) -> Rc<RefCell<W>> {
+        let type_id = TypeId::of::<W>();
+        let existing = self
+            .cells
+            .borrow()
+            .iter()
+            .rev()
+            .find(|(id, _)| *id == type_id)
+            .map(|(_, cell)| Rc::clone(cell));
+        let any = match existing {
+            Some(cell) => cell,
+            None => {
+                // The outer borrow is released while constructing the object, so that
+                // `W::default` may itself access the workspace
+                let cell: Rc<dyn Any> = Rc::new(RefCell::new(W::default()));
+                self.cells.borrow_mut().push((type_id, Rc::clone(&cell)));
+                cell
+            }
+        };
+        any.downcast()
+            .expect("Internal error: Cell type is determined by its TypeId")
+    }
+}
+
+impl fmt::Debug for ReentrantWorkspace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReentrantWorkspace")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Runs the provided closure with the object of type `W` from a thread-local
+/// [`ReentrantWorkspace`].
+///
+/// This mirrors [`with_thread_local_workspace`](crate::with_thread_local_workspace), but nested
+/// use with the same workspace variable is permitted as long as distinct types are accessed.
+/// See [`ReentrantWorkspace`] for the precise guarantee.
+///
+/// ## Panics
+///
+/// Panics if the object of type `W` is already in use further up the call stack.
+pub fn with_thread_local_reentrant_workspace<W: 'static + Default, T>(
+    workspace: &'static LocalKey<ReentrantWorkspace>,
+    f: impl FnOnce(&mut W) -> T,
+) -> T {
+    workspace.with(|workspace| workspace.with(f))
+}
+
+/// Helper macro for defining thread-local reentrant workspaces.
+///
+/// See [`ReentrantWorkspace`] for usage instructions.
+#[macro_export]
+macro_rules! define_thread_local_reentrant_workspace {
+    ($variable_name:ident) => {
+        thread_local! {
+            static $variable_name: $crate::ReentrantWorkspace = $crate::ReentrantWorkspace::new();
+        }
+    };
+}
@@ -0,0 +1,59 @@
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+/// Reusable state of a fast, non-cryptographic random number generator.
+///
+/// Seeding a generator from the operating system's entropy source is far more expensive than
+/// drawing a few numbers from it. Stored in a thread-local workspace, a `RngScratch` is seeded
+/// lazily on first use, and its [`SmallRng`] is reused by all subsequent calls on the same
+/// thread.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_thread_local_workspace, RngScratch};
+/// use rand::RngExt;
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// fn roll_die() -> u32 {
+///     with_thread_local_workspace(&WORKSPACE, |rng: &mut RngScratch| {
+///         rng.get().random_range(1..=6)
+///     })
+/// }
+///
+/// assert!((1..=6).contains(&roll_die()));
+/// ```
+///
+/// Note that each thread has its own generator, seeded independently. Results are therefore
+/// not reproducible from run to run, and depend on which thread performs which call. Code
+/// that needs reproducible results should seed explicitly with [`RngScratch::seed`].
+#[derive(Debug, Clone, Default)]
+pub struct RngScratch {
+    rng: Option<SmallRng>,
+}
+
+impl RngScratch {
+    /// Creates a new, not yet seeded generator state. Does not access the entropy source.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the generator, seeding it from the operating system's entropy source if this
+    /// is its first use.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if entropy can not be obtained from the operating system.
+    pub fn get(&mut self) -> &mut SmallRng {
+        self.rng.get_or_insert_with(rand::make_rng)
+    }
+
+    /// Replaces the generator with one seeded deterministically from `seed`.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = Some(SmallRng::seed_from_u64(seed));
+    }
+
+    /// Returns `true` if the generator has been seeded.
+    pub fn is_seeded(&self) -> bool {
+        self.rng.is_some()
+    }
+}
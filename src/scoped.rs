@@ -0,0 +1,157 @@
+use std::any::TypeId;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// A workspace for objects that borrow from the current stack frame.
+///
+/// [`Workspace`](crate::Workspace) requires its objects to be `'static`, since it identifies
+/// them by [`TypeId`]. This excludes objects that hold references, such as a scratch struct
+/// holding a `&'a [T]`. `ScopedWorkspace<'a>` instead holds objects that live at least as long
+/// as `'a`, and is itself confined to `'a`, so that it can never hand out an object after the
+/// data it borrows has gone away. It is not stored in thread-local storage, and is typically
+/// created with [`scope_with_borrowed_workspace`].
+///
+/// Since non-`'static` types have no [`TypeId`], each object is identified by a separate
+/// `'static` key type chosen by the caller. This is why access through
+/// [`ScopedWorkspace::get_or_insert_with`] is `unsafe`, see its documentation for the contract.
+///
+/// ## Soundness
+///
+/// The workspace is invariant in `'a`, and only accepts objects of types `W: 'a`. All objects
+/// are dropped when the workspace is dropped, which happens within `'a`, so no object is ever
+/// accessed or dropped after the data it borrows is gone. The remaining risk is that an object
+/// stored as type `W` is retrieved as a different type `V`, which would be undefined behavior.
+/// The workspace can not detect this for non-`'static` types, and relies on the caller to
+/// always use the same type with the same key.
+pub struct ScopedWorkspace<'a> {
+    entries: Vec<ScopedEntry>,
+    // Invariant in 'a, so that the workspace can neither be shortened nor extended
+    marker: PhantomData<&'a mut &'a ()>,
+}
+
+struct ScopedEntry {
+    key: TypeId,
+    // Obtained from `Box::<W>::into_raw`
+    ptr: *mut (),
+    drop: unsafe fn(*mut ()),
+}
+
+unsafe fn drop_boxed<W>(ptr: *mut ()) {
+    // SAFETY: The caller guarantees that `ptr` was obtained from `Box::<W>::into_raw`
+    drop(unsafe { Box::from_raw(ptr as *mut W) });
+}
+
+impl<'a> ScopedWorkspace<'a> {
+    /// Creates a new, empty workspace.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the object stored under the key type `K`, inserting the object returned by
+    /// `create` if no object is stored under `K`.
+    ///
+    /// ## Safety
+    ///
+    /// For any given workspace, all calls with the same key type `K` must use the exact same
+    /// type `W`, including all lifetime parameters. The simplest way to uphold this is to
+    /// define a dedicated key type for each object, next to the single call site that accesses
+    /// it with its type spelled out in full.
+    pub unsafe fn get_or_insert_with<K: 'static, W: 'a>(
+        &mut self,
+        create: impl FnOnce() -> W,
+    ) -> &mut W {
+        let key = TypeId::of::<K>();
+        let idx = match self.entries.iter().position(|entry| entry.key == key) {
+            Some(idx) => idx,
+            None => {
+                self.entries.push(ScopedEntry {
+                    key,
+                    ptr: Box::into_raw(Box::new(create())) as *mut (),
+                    drop: drop_boxed::<W>,
+                });
+                self.entries.len() - 1
+            }
+        };
+        // SAFETY: The caller guarantees that the entry stored under `K` has type `W`, and the
+        // pointer is valid and uniquely borrowed through `&mut self`
+        unsafe { &mut *(self.entries[idx].ptr as *mut W) }
+    }
+
+    /// Returns the number of objects stored in the workspace.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the workspace holds no objects.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<'a> Default for ScopedWorkspace<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Drop for ScopedWorkspace<'a> {
+    fn drop(&mut self) {
+        for entry in self.entries.drain(..) {
+            // SAFETY: `drop` was created for the type that `ptr` was boxed as, and each entry
+            // is dropped exactly once
+            unsafe { (entry.drop)(entry.ptr) };
+        }
+    }
+}
+
+impl<'a> fmt::Debug for ScopedWorkspace<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScopedWorkspace")
+            .field("len", &self.entries.len())
+            .finish()
+    }
+}
+
+/// Runs the provided closure with a [`ScopedWorkspace`] that is dropped when the closure
+/// returns.
+///
+/// This does not use thread-local storage. Instead, the workspace lives on the stack for the
+/// duration of the closure, and may hold objects that borrow from the enclosing scope.
+///
+/// ```rust
+/// use davenport::scope_with_borrowed_workspace;
+///
+/// struct Window<'a> {
+///     data: &'a [f64],
+///     sums: Vec<f64>,
+/// }
+///
+/// struct WindowKey;
+///
+/// let data = vec![1.0, 2.0, 3.0, 4.0];
+/// let total = scope_with_borrowed_workspace(|ws| {
+///     let mut total = 0.0;
+///     for width in 1..=2 {
+///         // SAFETY: WindowKey is only ever used with Window<'_> borrowing `data`
+///         let window = unsafe {
+///             ws.get_or_insert_with::<WindowKey, Window<'_>>(|| Window {
+///                 data: &data,
+///                 sums: Vec::new(),
+///             })
+///         };
+///         window.sums.clear();
+///         window
+///             .sums
+///             .extend(window.data.windows(width).map(|w| w.iter().sum::<f64>()));
+///         total += window.sums.iter().sum::<f64>();
+///     }
+///     total
+/// });
+/// assert_eq!(total, 25.0);
+/// ```
+pub fn scope_with_borrowed_workspace<'a, T>(f: impl FnOnce(&mut ScopedWorkspace<'a>) -> T) -> T {
+    f(&mut ScopedWorkspace::new())
+}
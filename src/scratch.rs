@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+/// A reusable [`HashMap`] for use as a dedup set or grouping map in workspaces.
+///
+/// Reusing the allocation of a map across calls requires remembering to clear it before use.
+/// [`ScratchMap::fresh`] codifies this: it returns the map cleared of any previous contents,
+/// while retaining its capacity.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_thread_local_workspace, ScratchMap};
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// fn count_distinct(values: &[u32]) -> usize {
+///     with_thread_local_workspace(&WORKSPACE, |scratch: &mut ScratchMap<u32, ()>| {
+///         let seen = scratch.fresh();
+///         seen.extend(values.iter().map(|&v| (v, ())));
+///         seen.len()
+///     })
+/// }
+///
+/// assert_eq!(count_distinct(&[1, 2, 2, 3]), 3);
+/// assert_eq!(count_distinct(&[4, 4]), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ScratchMap<K, V> {
+    map: HashMap<K, V>,
+}
+
+impl<K, V> ScratchMap<K, V> {
+    /// Creates a new, empty map. Does not allocate.
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    /// Clears the map and returns it, retaining its allocated capacity.
+    pub fn fresh(&mut self) -> &mut HashMap<K, V> {
+        self.map.clear();
+        &mut self.map
+    }
+
+    /// Returns the map without clearing it.
+    pub fn get(&self) -> &HashMap<K, V> {
+        &self.map
+    }
+
+    /// The number of elements the map can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+}
+
+impl<K, V> Default for ScratchMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A reusable `Vec<Vec<T>>`, which recycles the allocations of its inner vectors.
+///
+/// Simply clearing a `Vec<Vec<T>>` drops all inner vectors, and with them their allocations.
+/// [`RecyclingVecOfVecs::prepare`] instead clears the inner vectors while retaining their
+/// capacity. When fewer inner vectors are requested than in a previous call, the remaining
+/// ones are kept aside for later calls rather than dropped.
+///
+/// ```rust
+/// use davenport::{define_thread_local_workspace, with_thread_local_workspace};
+/// use davenport::RecyclingVecOfVecs;
+///
+/// define_thread_local_workspace!(WORKSPACE);
+///
+/// fn max_degree(edges: &[(usize, usize)], num_vertices: usize) -> usize {
+///     with_thread_local_workspace(&WORKSPACE, |scratch: &mut RecyclingVecOfVecs<usize>| {
+///         let adjacency = scratch.prepare(num_vertices);
+///         for &(a, b) in edges {
+///             adjacency[a].push(b);
+///             adjacency[b].push(a);
+///         }
+///         adjacency.iter().map(Vec::len).max().unwrap_or(0)
+///     })
+/// }
+///
+/// assert_eq!(max_degree(&[(0, 1), (0, 2)], 3), 2);
+/// assert_eq!(max_degree(&[(0, 1)], 2), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RecyclingVecOfVecs<T> {
+    // Only the first `len` vectors are handed out, the rest are kept for recycling
+    vecs: Vec<Vec<T>>,
+    len: usize,
+}
+
+impl<T> RecyclingVecOfVecs<T> {
+    /// Creates a new, empty instance. Does not allocate.
+    pub fn new() -> Self {
+        Self {
+            vecs: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns `outer_len` empty inner vectors, recycling the allocations of inner vectors
+    /// from previous calls.
+    pub fn prepare(&mut self, outer_len: usize) -> &mut [Vec<T>] {
+        if outer_len > self.vecs.len() {
+            self.vecs.resize_with(outer_len, Vec::new);
+        }
+        for inner in &mut self.vecs[..outer_len] {
+            inner.clear();
+        }
+        self.len = outer_len;
+        &mut self.vecs[..outer_len]
+    }
+
+    /// Returns the inner vectors handed out by the last call to
+    /// [`RecyclingVecOfVecs::prepare`], without clearing them.
+    pub fn get(&self) -> &[Vec<T>] {
+        &self.vecs[..self.len]
+    }
+
+    /// The number of inner vectors available, including those not currently in use.
+    pub fn num_allocated(&self) -> usize {
+        self.vecs.len()
+    }
+}
+
+impl<T> Default for RecyclingVecOfVecs<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,77 @@
+use crate::Workspace;
+use std::ops::{Deref, DerefMut};
+
+/// A scoped session of workspace use, which resets objects on each access and shrinks them
+/// when the session ends.
+///
+/// Obtained with [`Workspace::session`]. Objects with a reset function stored by
+/// [`Workspace::get_or_insert_with_resetter`] are reset on each access through
+/// [`WorkspaceSession::get`], so that every access starts from a clean object. When the
+/// session is dropped, [`Workspace::shrink_all`] applies the memory policies of all objects.
+///
+/// The session dereferences to the workspace, so that all other methods of [`Workspace`] are
+/// available as usual, without resetting.
+///
+/// ```rust
+/// use davenport::{MemoryPolicy, Workspace};
+///
+/// let mut ws = Workspace::default();
+/// let policy = MemoryPolicy::RetainAtMost { max_capacity: 16 };
+/// ws.get_or_default_with_policy::<Vec<u32>>(policy);
+/// ws.get_or_insert_with_resetter(Vec::<u32>::new, Vec::clear);
+///
+/// {
+///     let mut session = ws.session();
+///     for n in [100, 200] {
+///         let buffer = session.get::<Vec<u32>>();
+///         assert!(buffer.is_empty());
+///         buffer.extend(0..n);
+///     }
+/// }
+/// assert!(ws.try_get::<Vec<u32>>().unwrap().capacity() <= 16);
+/// ```
+#[derive(Debug)]
+pub struct WorkspaceSession<'a> {
+    workspace: &'a mut Workspace,
+}
+
+impl Workspace {
+    /// Opens a [`WorkspaceSession`] on the workspace.
+    pub fn session(&mut self) -> WorkspaceSession<'_> {
+        WorkspaceSession { workspace: self }
+    }
+}
+
+impl WorkspaceSession<'_> {
+    /// Returns the object of type `W`, inserting a default-constructed object if not present,
+    /// and resetting it with its stored reset function, if any.
+    pub fn get<W: 'static + Default>(&mut self) -> &mut W {
+        let entry = self.workspace.get_or_insert_entry_with(W::default);
+        if let Some(reset) = &entry.resetter {
+            reset(&mut *entry.value);
+        }
+        entry
+            .downcast_mut()
+            .expect("Internal error: Downcasting can by definition not fail")
+    }
+}
+
+impl Deref for WorkspaceSession<'_> {
+    type Target = Workspace;
+
+    fn deref(&self) -> &Workspace {
+        self.workspace
+    }
+}
+
+impl DerefMut for WorkspaceSession<'_> {
+    fn deref_mut(&mut self) -> &mut Workspace {
+        self.workspace
+    }
+}
+
+impl Drop for WorkspaceSession<'_> {
+    fn drop(&mut self) {
+        self.workspace.shrink_all();
+    }
+}
@@ -0,0 +1,269 @@
+use crate::Workspace;
+use std::cell::{RefCell, RefMut};
+use std::error::Error;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+/// A handle to a [`Workspace`] shared between several owners on the same thread.
+///
+/// Cloning the handle yields another handle to the same workspace. This is an alternative to
+/// thread-local workspaces for code that prefers to pass the workspace around explicitly, e.g.
+/// between components that are constructed separately but should share their buffers. Like
+/// [`Workspace`] itself, the handle is neither [`Send`] nor [`Sync`].
+///
+/// Access is exclusive: while the workspace is in use through one handle, it can not be used
+/// through another. [`SharedWorkspace::with`] panics in this case, whereas
+/// [`SharedWorkspace::try_with`] reports it as an error.
+///
+/// ```rust
+/// use davenport::SharedWorkspace;
+///
+/// let shared = SharedWorkspace::new();
+/// let other = shared.clone();
+/// shared.with(|buffer: &mut Vec<u32>| buffer.push(1));
+/// assert_eq!(other.with(|buffer: &mut Vec<u32>| buffer.len()), 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SharedWorkspace {
+    inner: Rc<RefCell<Workspace>>,
+}
+
+impl SharedWorkspace {
+    /// Creates a handle to a new, empty workspace.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the provided closure with the object of type `W`, inserting a default-constructed
+    /// object if not present.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the workspace is already in use through this or another handle.
+    pub fn with<W: 'static + Default, T>(&self, f: impl FnOnce(&mut W) -> T) -> T {
+        let mut workspace = self
+            .inner
+            .try_borrow_mut()
+            .expect("Can not use a shared workspace that is already in use.");
+        f(workspace.get_or_default())
+    }
+
+    /// Same as [`SharedWorkspace::with`], but for fallible closures, and reports a workspace that
+    /// is already in use as an error instead of panicking.
+    ///
+    /// Errors of the closure and failure to access the workspace are combined in a
+    /// [`SharedWorkspaceError`], so that both can be propagated with `?`.
+    ///
+    /// ```rust
+    /// use davenport::{SharedWorkspace, SharedWorkspaceError};
+    ///
+    /// fn parse_all(
+    ///     shared: &SharedWorkspace,
+    ///     input: &str,
+    /// ) -> Result<u32, SharedWorkspaceError<std::num::ParseIntError>> {
+    ///     shared.try_with(|values: &mut Vec<u32>| {
+    ///         values.clear();
+    ///         for token in input.split(',') {
+    ///             values.push(token.parse()?);
+    ///         }
+    ///         Ok(values.iter().sum())
+    ///     })
+    /// }
+    ///
+    /// let shared = SharedWorkspace::new();
+    /// assert_eq!(parse_all(&shared, "1,2,3"), Ok(6));
+    /// assert!(matches!(parse_all(&shared, "1,x"), Err(SharedWorkspaceError::Closure(_))));
+    /// ```
+    pub fn try_with<W: 'static + Default, T, E>(
+        &self,
+        f: impl FnOnce(&mut W) -> Result<T, E>,
+    ) -> Result<T, SharedWorkspaceError<E>> {
+        let mut workspace = self.inner.try_borrow_mut().map_err(|_| WorkspaceBusy)?;
+        f(workspace.get_or_default()).map_err(SharedWorkspaceError::Closure)
+    }
+
+    /// Returns a guard providing access to the object of type `W`, inserting a
+    /// default-constructed object if not present.
+    ///
+    /// The workspace is in use until the guard is dropped. The guard can be narrowed down to
+    /// part of the object with [`WorkspaceGuard::map`].
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the workspace is already in use through this or another handle.
+    pub fn get_or_default<W: 'static + Default>(&self) -> WorkspaceGuard<'_, W> {
+        self.try_get_or_default()
+            .expect("Can not use a shared workspace that is already in use.")
+    }
+
+    /// Same as [`SharedWorkspace::get_or_default`], but returns an error instead of panicking
+    /// if the workspace is already in use.
+    pub fn try_get_or_default<W: 'static + Default>(
+        &self,
+    ) -> Result<WorkspaceGuard<'_, W>, WorkspaceBusy> {
+        let workspace = self.inner.try_borrow_mut().map_err(|_| WorkspaceBusy)?;
+        Ok(WorkspaceGuard {
+            inner: RefMut::map(workspace, Workspace::get_or_default),
+        })
+    }
+
+    /// Returns `true` if the workspace is currently in use through any handle.
+    pub fn is_in_use(&self) -> bool {
+        self.inner.try_borrow_mut().is_err()
+    }
+}
+
+/// Exclusive access to an object stored in a [`SharedWorkspace`], or to part of it.
+///
+/// Obtained with [`SharedWorkspace::get_or_default`]. The workspace is in use until the guard
+/// is dropped.
+pub struct WorkspaceGuard<'a, W: ?Sized> {
+    inner: RefMut<'a, W>,
+}
+
+impl<'a, W: ?Sized> WorkspaceGuard<'a, W> {
+    /// Narrows the guard down to a part of the guarded object, such as one of its fields.
+    ///
+    /// This mirrors `RefMut::map` in the standard library, and `MutexGuard::map` of
+    /// `parking_lot`. Unlike those, it is a method, so it takes precedence over a method named
+    /// `map` of the guarded object, which must then be called through explicit dereferencing.
+    ///
+    /// ```rust
+    /// use davenport::SharedWorkspace;
+    ///
+    /// #[derive(Default)]
+    /// struct MyWorkspace {
+    ///     index_buffer: Vec<usize>,
+    ///     values: Vec<f64>,
+    /// }
+    ///
+    /// let shared = SharedWorkspace::new();
+    /// let mut indices = shared
+    ///     .get_or_default::<MyWorkspace>()
+    ///     .map(|w| &mut w.index_buffer);
+    /// indices.push(3);
+    /// ```
+    pub fn map<U: ?Sized>(self, f: impl FnOnce(&mut W) -> &mut U) -> WorkspaceGuard<'a, U> {
+        WorkspaceGuard {
+            inner: RefMut::map(self.inner, f),
+        }
+    }
+
+    /// Gives up mutable access to the guarded object, retaining shared access.
+    ///
+    /// The workspace remains in use until the returned [`WorkspaceReadGuard`] is dropped, so it
+    /// can not be read through other handles in the meantime. The downgrade merely guarantees,
+    /// e.g. to read-only helpers, that the object no longer changes.
+    ///
+    /// ```rust
+    /// use davenport::SharedWorkspace;
+    ///
+    /// fn sum(values: &[u32]) -> u32 {
+    ///     values.iter().sum()
+    /// }
+    ///
+    /// let shared = SharedWorkspace::new();
+    /// let mut values = shared.get_or_default::<Vec<u32>>();
+    /// values.extend([1, 2, 3]);
+    /// let values = values.downgrade();
+    /// assert_eq!(sum(&values), 6);
+    /// assert_eq!(values.len(), 3);
+    /// ```
+    pub fn downgrade(self) -> WorkspaceReadGuard<'a, W> {
+        WorkspaceReadGuard { inner: self.inner }
+    }
+}
+
+impl<W: ?Sized> Deref for WorkspaceGuard<'_, W> {
+    type Target = W;
+
+    fn deref(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: ?Sized> DerefMut for WorkspaceGuard<'_, W> {
+    fn deref_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: ?Sized + fmt::Debug> fmt::Debug for WorkspaceGuard<'_, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WorkspaceGuard")
+            .field(&&*self.inner)
+            .finish()
+    }
+}
+
+/// Shared access to an object stored in a [`SharedWorkspace`], or to part of it.
+///
+/// Obtained with [`WorkspaceGuard::downgrade`]. The workspace is in use until the guard is
+/// dropped.
+pub struct WorkspaceReadGuard<'a, W: ?Sized> {
+    // The standard library provides no way to downgrade a `RefMut`, so the mutable borrow is
+    // kept and only shared access is exposed
+    inner: RefMut<'a, W>,
+}
+
+impl<W: ?Sized> Deref for WorkspaceReadGuard<'_, W> {
+    type Target = W;
+
+    fn deref(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: ?Sized + fmt::Debug> fmt::Debug for WorkspaceReadGuard<'_, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WorkspaceReadGuard")
+            .field(&&*self.inner)
+            .finish()
+    }
+}
+
+/// Error indicating that a [`SharedWorkspace`] is already in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkspaceBusy;
+
+impl fmt::Display for WorkspaceBusy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "shared workspace is already in use")
+    }
+}
+
+impl Error for WorkspaceBusy {}
+
+/// Error returned by [`SharedWorkspace::try_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SharedWorkspaceError<E> {
+    /// The workspace was already in use, so the closure was not run.
+    Busy(WorkspaceBusy),
+    /// The closure returned an error.
+    Closure(E),
+}
+
+impl<E> From<WorkspaceBusy> for SharedWorkspaceError<E> {
+    fn from(busy: WorkspaceBusy) -> Self {
+        Self::Busy(busy)
+    }
+}
+
+impl<E> fmt::Display for SharedWorkspaceError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Busy(busy) => busy.fmt(f),
+            Self::Closure(_) => write!(f, "closure using shared workspace failed"),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for SharedWorkspaceError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Busy(_) => None,
+            Self::Closure(err) => Some(err),
+        }
+    }
+}
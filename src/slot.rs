@@ -0,0 +1,79 @@
+use std::fmt;
+
+/// A slot holding at most one trait object of type `Box<T>`, for storage in a workspace.
+///
+/// A [`Workspace`](crate::Workspace) keys objects by their concrete type, so storing a
+/// `Box<dyn Trait>` directly ties its retrieval to the boxed type chosen at insertion.
+/// `TraitSlot<dyn Trait>` is instead keyed by the trait alone, which allows swapping the
+/// concrete implementation at runtime, e.g. for a per-thread strategy.
+///
+/// ```rust
+/// use davenport::{TraitSlot, Workspace};
+///
+/// trait Strategy {
+///     fn apply(&self, x: f64) -> f64;
+/// }
+///
+/// struct Double;
+///
+/// impl Strategy for Double {
+///     fn apply(&self, x: f64) -> f64 {
+///         2.0 * x
+///     }
+/// }
+///
+/// let mut ws = Workspace::default();
+/// let slot = ws.get_or_default::<TraitSlot<dyn Strategy>>();
+/// assert!(slot.get_mut().is_none());
+/// slot.set(Box::new(Double));
+/// assert_eq!(slot.get_mut().unwrap().apply(1.5), 3.0);
+/// ```
+pub struct TraitSlot<T: ?Sized> {
+    value: Option<Box<T>>,
+}
+
+impl<T: ?Sized> TraitSlot<T> {
+    /// Creates a new, empty slot.
+    pub const fn new() -> Self {
+        Self { value: None }
+    }
+
+    /// Stores the given object in the slot, returning the previously stored object, if any.
+    pub fn set(&mut self, value: Box<T>) -> Option<Box<T>> {
+        self.value.replace(value)
+    }
+
+    /// Returns the stored object, if any.
+    pub fn get(&self) -> Option<&T> {
+        self.value.as_deref()
+    }
+
+    /// Returns the stored object, if any.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.value.as_deref_mut()
+    }
+
+    /// Removes the stored object from the slot, and returns it.
+    pub fn take(&mut self) -> Option<Box<T>> {
+        self.value.take()
+    }
+
+    /// Returns `true` if the slot holds an object.
+    pub fn is_set(&self) -> bool {
+        self.value.is_some()
+    }
+}
+
+impl<T: ?Sized> Default for TraitSlot<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for TraitSlot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraitSlot")
+            .field("is_set", &self.is_set())
+            .finish()
+    }
+}
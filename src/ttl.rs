@@ -0,0 +1,55 @@
+use crate::Workspace;
+use std::time::{Duration, Instant};
+
+impl Workspace {
+    /// Removes all objects that have not been accessed within the given duration, and returns
+    /// the number of removed objects.
+    ///
+    /// Unlike [`Workspace::entries_idle_for`], which measures staleness in workspace accesses,
+    /// this measures it in wall-clock time. This allows reclaiming memory held by long-lived
+    /// threads that only occasionally perform a particular computation. Objects count as
+    /// accessed when they are inserted, and on each access through
+    /// [`Workspace::get_or_insert_with`] and the methods built on it.
+    ///
+    /// ```rust
+    /// use davenport::Workspace;
+    /// use std::time::Duration;
+    ///
+    /// let mut ws = Workspace::default();
+    /// ws.get_or_default::<Vec<f64>>().reserve(100);
+    /// assert_eq!(ws.evict_older_than(Duration::from_secs(60)), 0);
+    /// assert_eq!(ws.evict_older_than(Duration::ZERO), 1);
+    /// ```
+    pub fn evict_older_than(&mut self, ttl: Duration) -> usize {
+        let now = self.now();
+        let mut num_evicted = 0;
+        let mut idx = 0;
+        while idx < self.workspaces.len() {
+            let last_access = self.workspaces[idx]
+                .last_access_time
+                .expect("Internal error: Entries in a workspace always have an access time");
+            if now.saturating_duration_since(last_access) >= ttl {
+                self.discard_at(idx);
+                num_evicted += 1;
+            } else {
+                idx += 1;
+            }
+        }
+        num_evicted
+    }
+
+    /// Replaces the clock used to record access times, which defaults to [`Instant::now`].
+    ///
+    /// This is mainly useful for testing time-based eviction deterministically. Access times
+    /// already recorded are kept, so the new clock should not run behind the previous one.
+    pub fn set_clock(&mut self, clock: Box<dyn Fn() -> Instant>) {
+        self.clock = Some(clock);
+    }
+
+    pub(crate) fn now(&self) -> Instant {
+        match &self.clock {
+            Some(clock) => clock(),
+            None => Instant::now(),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "bumpalo")]
+
+use davenport::{define_thread_local_workspace, with_thread_local_bump, BumpScratch, Workspace};
+
+#[test]
+fn reset_reclaims_space_and_retains_chunk() {
+    let mut ws = Workspace::default();
+    let scratch: &mut BumpScratch = ws.get_or_default();
+    for i in 0..1000u64 {
+        scratch.bump().alloc(i);
+    }
+    let allocated = scratch.allocated_bytes();
+    assert!(allocated >= 1000 * 8);
+
+    scratch.reset();
+    let retained = scratch.allocated_bytes();
+    assert!(retained > 0);
+    assert!(retained <= allocated);
+
+    // Allocations after the reset are served from the retained chunk
+    let scratch: &mut BumpScratch = ws.get_or_default();
+    for i in 0..100u64 {
+        scratch.bump().alloc(i);
+    }
+    assert_eq!(scratch.allocated_bytes(), retained);
+}
+
+define_thread_local_workspace!(BUMP_WORKSPACE);
+
+#[test]
+fn thread_local_bump_is_reset_after_use() {
+    let remaining_capacities: Vec<_> = (0..3)
+        .map(|_| {
+            with_thread_local_bump(&BUMP_WORKSPACE, |bump| {
+                let before = bump.chunk_capacity();
+                bump.alloc_slice_fill_copy(256, 0u8);
+                before
+            })
+        })
+        .collect();
+    // Each use starts from an empty arena, so the capacity available up front never shrinks
+    assert!(remaining_capacities.windows(2).all(|w| w[1] >= w[0]));
+
+    BUMP_WORKSPACE.with(|ws| {
+        let mut ws = ws.borrow_mut();
+        let scratch: &mut BumpScratch = ws.get_or_default();
+        let capacity = scratch.bump().chunk_capacity();
+        scratch.bump().alloc_slice_fill_copy(256, 0u8);
+        assert!(scratch.bump().chunk_capacity() < capacity);
+    });
+}
@@ -0,0 +1,31 @@
+#![cfg(feature = "debug-backtrace")]
+
+use davenport::Workspace;
+
+#[inline(never)]
+fn first_access(ws: &mut Workspace) {
+    ws.get_or_default::<Vec<u8>>().push(1);
+}
+
+#[inline(never)]
+fn second_access(ws: &mut Workspace) {
+    ws.get_or_default::<Vec<u8>>().push(2);
+}
+
+#[test]
+fn backtrace_is_recorded_on_creation_only() {
+    let mut ws = Workspace::default();
+    assert!(ws.creation_backtrace::<Vec<u8>>().is_none());
+
+    first_access(&mut ws);
+    let backtrace = ws.creation_backtrace::<Vec<u8>>().unwrap().to_string();
+    assert!(backtrace.contains("first_access"));
+
+    second_access(&mut ws);
+    let backtrace = ws.creation_backtrace::<Vec<u8>>().unwrap().to_string();
+    assert!(backtrace.contains("first_access"));
+    assert!(!backtrace.contains("second_access"));
+
+    assert!(ws.try_insert(1u32).is_some());
+    assert!(ws.creation_backtrace::<u32>().is_none());
+}
@@ -0,0 +1,19 @@
+#![cfg(feature = "debug-names")]
+
+use davenport::Workspace;
+
+#[test]
+fn type_name_check_accepts_matching_types() {
+    struct Generic<T>(T);
+
+    let mut ws = Workspace::default();
+    for i in 0..3 {
+        ws.get_or_default::<Vec<Option<u8>>>().push(Some(i));
+        ws.get_or_insert_with(|| Generic(0u16)).0 += 1;
+        ws.get_or_insert_with(|| Generic(0i16)).0 -= 1;
+        let _ = ws.get_keyed::<u8, String>();
+    }
+    assert_eq!(ws.get_or_default::<Vec<Option<u8>>>().len(), 3);
+    assert_eq!(ws.try_get::<Generic<u16>>().unwrap().0, 3);
+    assert_eq!(ws.try_get::<Generic<i16>>().unwrap().0, -3);
+}
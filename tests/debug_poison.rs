@@ -0,0 +1,16 @@
+#![cfg(feature = "debug-poison")]
+
+use davenport::{define_thread_local_workspace, with_thread_local_workspace_poisoned};
+
+define_thread_local_workspace!(WORKSPACE);
+
+#[test]
+fn poisoned_contents_appear_on_reuse() {
+    with_thread_local_workspace_poisoned(&WORKSPACE, |buffer: &mut Vec<u32>| {
+        buffer.extend([1, 2, 3]);
+    });
+    // A caller forgetting to clear the buffer sees the sentinel rather than stale data
+    with_thread_local_workspace_poisoned(&WORKSPACE, |buffer: &mut Vec<u32>| {
+        assert_eq!(buffer, &[0xDE, 0xDE, 0xDE]);
+    });
+}
@@ -0,0 +1,82 @@
+//! Stress test for workspaces holding a large number of distinct types.
+
+use davenport::Workspace;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+struct Digit<const N: usize>;
+
+struct Marker<T>(usize, PhantomData<T>);
+
+impl<T> Default for Marker<T> {
+    fn default() -> Self {
+        Self(0, PhantomData)
+    }
+}
+
+macro_rules! for_each_digit {
+    ($f:ident[$($prefix:ty),*]($ws:expr)) => {
+        $f::<$($prefix,)* Digit<0>>($ws);
+        $f::<$($prefix,)* Digit<1>>($ws);
+        $f::<$($prefix,)* Digit<2>>($ws);
+        $f::<$($prefix,)* Digit<3>>($ws);
+        $f::<$($prefix,)* Digit<4>>($ws);
+        $f::<$($prefix,)* Digit<5>>($ws);
+        $f::<$($prefix,)* Digit<6>>($ws);
+        $f::<$($prefix,)* Digit<7>>($ws);
+        $f::<$($prefix,)* Digit<8>>($ws);
+        $f::<$($prefix,)* Digit<9>>($ws);
+    };
+}
+
+fn visit<A: 'static, B: 'static, C: 'static>(ws: &mut Workspace) {
+    let marker = ws.get_or_default::<Marker<(A, B, C)>>();
+    marker.0 += 1;
+}
+
+fn visit_level2<A: 'static, B: 'static>(ws: &mut Workspace) {
+    for_each_digit!(visit[A, B](ws));
+}
+
+fn visit_level1<A: 'static>(ws: &mut Workspace) {
+    for_each_digit!(visit_level2[A](ws));
+}
+
+fn visit_all(ws: &mut Workspace) {
+    for_each_digit!(visit_level1[](ws));
+}
+
+#[test]
+fn thousand_distinct_types() {
+    let mut ws = Workspace::default();
+    ws.reserve(1000);
+
+    let start = Instant::now();
+    visit_all(&mut ws);
+    visit_all(&mut ws);
+    let elapsed = start.elapsed();
+
+    assert_eq!(
+        ws.get_or_default::<Marker<(Digit<0>, Digit<0>, Digit<0>)>>()
+            .0,
+        2
+    );
+    assert_eq!(
+        ws.get_or_default::<Marker<(Digit<4>, Digit<2>, Digit<7>)>>()
+            .0,
+        2
+    );
+    assert_eq!(
+        ws.get_or_default::<Marker<(Digit<9>, Digit<9>, Digit<9>)>>()
+            .0,
+        2
+    );
+
+    // Two passes over 1000 types cost about two million type comparisons with the linear scan.
+    // This bound is very generous, but catches anything substantially worse than that.
+    assert!(
+        elapsed < Duration::from_secs(5),
+        "Populating workspace took {:?}",
+        elapsed
+    );
+}
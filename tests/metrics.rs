@@ -0,0 +1,115 @@
+#![cfg(feature = "metrics")]
+
+use davenport::{TypeStats, Workspace};
+use log::{Level, Log, Metadata, Record};
+use std::any::TypeId;
+use std::sync::Mutex;
+
+fn stats_of<W: 'static>(ws: &Workspace) -> TypeStats {
+    ws.export_stats()
+        .into_iter()
+        .find(|(id, _)| *id == TypeId::of::<W>())
+        .map(|(_, stats)| stats)
+        .unwrap()
+}
+
+#[test]
+fn export_stats_reports_per_type_counts() {
+    let mut ws = Workspace::default();
+    ws.register_memory_usage::<Vec<u64>>();
+    assert!(ws.export_stats().is_empty());
+
+    for _ in 0..5 {
+        ws.get_or_default::<Vec<u64>>().reserve(16);
+    }
+    for _ in 0..2 {
+        let _ = ws.get_or_default::<String>();
+    }
+
+    assert_eq!(ws.export_stats().len(), 2);
+    let vec_stats = stats_of::<Vec<u64>>(&ws);
+    assert_eq!(vec_stats.hits, 4);
+    assert_eq!(vec_stats.misses, 1);
+    assert_eq!(vec_stats.creations, 1);
+    assert!(vec_stats.peak_bytes.unwrap() >= 16 * 8);
+
+    let string_stats = stats_of::<String>(&ws);
+    assert_eq!(
+        string_stats,
+        TypeStats {
+            hits: 1,
+            misses: 1,
+            creations: 1,
+            peak_bytes: None,
+        }
+    );
+}
+
+#[test]
+fn export_stats_survive_removal() {
+    let mut ws = Workspace::default();
+    let _ = ws.get_or_default::<u32>();
+    ws.clear();
+    let _ = ws.get_or_default::<u32>();
+    assert!(ws.try_insert(1u8).is_some());
+
+    let stats = stats_of::<u32>(&ws);
+    assert_eq!((stats.hits, stats.misses, stats.creations), (0, 2, 2));
+    let stats = stats_of::<u8>(&ws);
+    assert_eq!((stats.hits, stats.misses, stats.creations), (0, 0, 1));
+}
+
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct RecordingLogger;
+
+impl Log for RecordingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            WARNINGS.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: RecordingLogger = RecordingLogger;
+
+fn thrash_warnings() -> usize {
+    WARNINGS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|warning| warning.contains("alternated between two types"))
+        .count()
+}
+
+#[test]
+fn alternating_access_warns_once() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Warn);
+
+    let mut ws = Workspace::default();
+    ws.set_swap_thrash_threshold(10);
+    for _ in 0..5 {
+        let _ = ws.get_or_default::<Vec<u32>>();
+        let _ = ws.get_or_default::<Vec<f64>>();
+    }
+    // Repeated access to the same type breaks the alternation
+    let _ = ws.get_or_default::<Vec<f64>>();
+    for _ in 0..5 {
+        let _ = ws.get_or_default::<Vec<u32>>();
+        let _ = ws.get_or_default::<Vec<f64>>();
+    }
+    assert_eq!(thrash_warnings(), 0);
+
+    for _ in 0..100 {
+        let _ = ws.get_or_default::<Vec<u32>>();
+        let _ = ws.get_or_default::<Vec<f64>>();
+    }
+    assert_eq!(thrash_warnings(), 1);
+}
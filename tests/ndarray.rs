@@ -0,0 +1,66 @@
+#![cfg(feature = "ndarray")]
+
+use davenport::{CapacityReport, MemoryPolicy, ScratchArray2, Workspace};
+
+#[test]
+fn same_shape_reuses_allocation() {
+    let mut scratch = ScratchArray2::<f64>::new();
+    let ptr = {
+        let mut view = scratch.prepare((3, 4));
+        assert_eq!(view.dim(), (3, 4));
+        view.fill(1.0);
+        view.as_ptr()
+    };
+    let capacity = scratch.capacity();
+
+    let view = scratch.prepare((3, 4));
+    assert_eq!(view.as_ptr(), ptr);
+    assert!(view.iter().all(|&x| x == 1.0));
+    assert_eq!(scratch.capacity(), capacity);
+}
+
+#[test]
+fn smaller_shape_reuses_allocation() {
+    let mut scratch = ScratchArray2::<f64>::new();
+    let ptr = scratch.prepare((4, 4)).as_ptr();
+    let capacity = scratch.capacity();
+
+    let view = scratch.prepare((2, 5));
+    assert_eq!(view.dim(), (2, 5));
+    assert!(view.is_standard_layout());
+    assert_eq!(view.as_ptr(), ptr);
+    assert_eq!(scratch.capacity(), capacity);
+}
+
+#[test]
+fn larger_shape_reallocates() {
+    let mut scratch = ScratchArray2::<u32>::new();
+    scratch.prepare((2, 2)).fill(7);
+    let capacity = scratch.capacity();
+
+    let view = scratch.prepare((10, 10));
+    assert_eq!(view.dim(), (10, 10));
+    assert!(scratch.capacity() >= 100);
+    assert!(scratch.capacity() > capacity);
+
+    // Elements in the flat buffer are preserved, and new elements are defaulted
+    let view = scratch.prepare((10, 10));
+    let flat: Vec<u32> = view.iter().copied().collect();
+    assert_eq!(&flat[..4], &[7; 4]);
+    assert!(flat[4..].iter().all(|&x| x == 0));
+}
+
+#[test]
+fn shrink_after_use_policy_releases_memory() {
+    let mut ws = Workspace::default();
+    let policy = MemoryPolicy::ShrinkAfterUse { keep_ratio: 1.0 };
+    ws.get_or_default_with_policy::<ScratchArray2<f64>>(policy)
+        .prepare((100, 100));
+    ws.apply_memory_policy::<ScratchArray2<f64>>();
+    assert!(ws.try_get::<ScratchArray2<f64>>().unwrap().capacity() >= 10_000);
+
+    ws.get_or_default_with_policy::<ScratchArray2<f64>>(policy)
+        .prepare((2, 2));
+    ws.apply_memory_policy::<ScratchArray2<f64>>();
+    assert!(ws.try_get::<ScratchArray2<f64>>().unwrap().capacity() < 100);
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "rand")]
+
+use davenport::{define_thread_local_workspace, with_thread_local_workspace, RngScratch};
+use rand::{Rng, SeedableRng};
+
+#[test]
+fn rng_is_seeded_once_and_reused_across_calls() {
+    define_thread_local_workspace!(WORKSPACE);
+
+    let (first_ptr, snapshot) = with_thread_local_workspace(&WORKSPACE, |rng: &mut RngScratch| {
+        assert!(!rng.is_seeded());
+        rng.get().next_u64();
+        assert!(rng.is_seeded());
+        (rng.get() as *const _, rng.get().clone())
+    });
+
+    let (second_ptr, value) = with_thread_local_workspace(&WORKSPACE, |rng: &mut RngScratch| {
+        (rng.get() as *const _, rng.get().next_u64())
+    });
+
+    assert_eq!(first_ptr, second_ptr);
+    // The generator continues its stream instead of being reseeded
+    let mut snapshot = snapshot;
+    assert_eq!(value, snapshot.next_u64());
+}
+
+#[test]
+fn explicit_seed_is_reproducible() {
+    let mut a = RngScratch::new();
+    let mut b = RngScratch::new();
+    a.seed(42);
+    b.seed(42);
+    assert_eq!(a.get().next_u64(), b.get().next_u64());
+
+    let mut expected = rand::rngs::SmallRng::seed_from_u64(42);
+    expected.next_u64();
+    assert_eq!(a.get().next_u64(), expected.next_u64());
+}
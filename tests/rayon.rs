@@ -0,0 +1,33 @@
+#![cfg(feature = "rayon")]
+
+use davenport::{
+    clear_workspace_on_all_threads, define_thread_local_workspace, with_thread_local_workspace,
+    with_thread_local_workspace_raw,
+};
+
+define_thread_local_workspace!(WORKSPACE);
+
+fn num_objects_per_thread(pool: &rayon::ThreadPool) -> Vec<usize> {
+    pool.broadcast(|_| {
+        with_thread_local_workspace_raw(&WORKSPACE, |ws| ws.iter_of_type::<Vec<u8>>().count())
+    })
+}
+
+#[test]
+fn clear_workspace_on_all_threads_empties_every_worker() {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(3)
+        .build()
+        .unwrap();
+    pool.broadcast(|_| {
+        with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u8>| buffer.resize(1024, 0));
+    });
+    assert_eq!(num_objects_per_thread(&pool), [1, 1, 1]);
+
+    clear_workspace_on_all_threads(&pool, &WORKSPACE);
+    assert_eq!(num_objects_per_thread(&pool), [0, 0, 0]);
+    let capacities = pool.broadcast(|_| {
+        with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u8>| buffer.capacity())
+    });
+    assert_eq!(capacities, [0, 0, 0]);
+}
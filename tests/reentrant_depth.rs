@@ -0,0 +1,40 @@
+//! The maximum reentrant depth is process-wide, so it is tested in its own test binary to
+//! avoid interfering with other tests.
+
+use davenport::{
+    catch_workspace, define_thread_local_workspace, set_max_reentrant_depth,
+    with_thread_local_workspace_async_safe,
+};
+
+define_thread_local_workspace!(WORKSPACE);
+
+fn recurse(remaining: usize, max_seen_len: &mut usize) {
+    with_thread_local_workspace_async_safe(&WORKSPACE, |buffer: &mut Vec<usize>| {
+        buffer.push(remaining);
+        *max_seen_len = (*max_seen_len).max(buffer.len());
+        if remaining > 0 {
+            recurse(remaining - 1, max_seen_len);
+        }
+    })
+}
+
+#[test]
+fn fallback_panics_beyond_max_reentrant_depth() {
+    set_max_reentrant_depth(3);
+
+    // One use of the thread-local workspace, and three nested fallbacks
+    let mut max_seen_len = 0;
+    recurse(3, &mut max_seen_len);
+    assert_eq!(max_seen_len, 1);
+
+    let err = catch_workspace(|| recurse(4, &mut 0)).unwrap_err();
+    assert_eq!(
+        err.message(),
+        Some("Exceeded the maximum reentrant workspace depth of 3.")
+    );
+
+    // The depth is restored as the panic unwinds, so the limit applies afresh
+    recurse(3, &mut 0);
+    set_max_reentrant_depth(usize::MAX);
+    recurse(10, &mut 0);
+}
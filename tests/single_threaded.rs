@@ -0,0 +1,86 @@
+#![cfg(feature = "single-threaded")]
+
+use davenport::{
+    is_thread_local_workspace_borrowed, with_global_workspace, with_thread_local_workspace,
+    with_thread_local_workspace_with_policy, GlobalWorkspace, MemoryPolicy,
+};
+
+// SAFETY: WORKSPACE is only accessed from the single test below
+static WORKSPACE: GlobalWorkspace = unsafe { GlobalWorkspace::new() };
+
+#[test]
+fn global_workspace_reuse() {
+    with_global_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| {
+        assert!(buffer.is_empty());
+        buffer.push(1)
+    });
+    with_global_workspace(&WORKSPACE, |other: &mut Vec<u8>| other.push(2));
+    let contents = with_global_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| {
+        buffer.push(3);
+        buffer.clone()
+    });
+    assert_eq!(contents, vec![1, 3]);
+    with_global_workspace(&WORKSPACE, |other: &mut Vec<u8>| assert_eq!(other, &[2]));
+}
+
+// Defined the same way as by `define_thread_local_workspace!` with the `wasm-single-thread`
+// feature on WebAssembly targets without threads.
+// SAFETY: STATIC_WORKSPACE is only accessed from the single test below
+static STATIC_WORKSPACE: GlobalWorkspace = unsafe { GlobalWorkspace::new() };
+
+#[test]
+fn global_workspace_serves_as_workspace_key() {
+    let ptr = with_thread_local_workspace(&STATIC_WORKSPACE, |buffer: &mut Vec<u32>| {
+        buffer.extend([1, 2, 3]);
+        buffer.as_ptr()
+    });
+    with_thread_local_workspace(&STATIC_WORKSPACE, |buffer: &mut Vec<u32>| {
+        assert_eq!(buffer, &[1, 2, 3]);
+        assert_eq!(buffer.as_ptr(), ptr);
+    });
+    assert!(!is_thread_local_workspace_borrowed(&STATIC_WORKSPACE));
+
+    // Memory policies are applied after use, as for thread-local workspaces
+    let policy = MemoryPolicy::RetainAtMost { max_capacity: 8 };
+    with_thread_local_workspace_with_policy(&STATIC_WORKSPACE, policy, |s: &mut String| {
+        s.push_str(&"x".repeat(100))
+    });
+    let capacity = with_global_workspace(&STATIC_WORKSPACE, |s: &mut String| {
+        s.push_str(&"y".repeat(100));
+        s.len()
+    });
+    assert_eq!(capacity, 108);
+    with_global_workspace(&STATIC_WORKSPACE, |s: &mut String| {
+        assert!(s.capacity() <= 8)
+    });
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "Workspace ASSERTED_WORKSPACE is currently in use")]
+fn debug_assert_workspace_free_accepts_global_workspace() {
+    // SAFETY: ASSERTED_WORKSPACE is only accessible from this test
+    static ASSERTED_WORKSPACE: GlobalWorkspace = unsafe { GlobalWorkspace::new() };
+
+    davenport::debug_assert_workspace_free!(ASSERTED_WORKSPACE);
+    with_thread_local_workspace(&ASSERTED_WORKSPACE, |_: &mut Vec<u32>| {
+        davenport::debug_assert_workspace_free!(ASSERTED_WORKSPACE);
+    });
+}
+
+#[test]
+#[cfg(all(debug_assertions, feature = "debug-global-thread"))]
+fn global_workspace_access_from_other_thread_panics() {
+    // SAFETY: The check panics before the other thread touches the workspace
+    static CHECKED_WORKSPACE: GlobalWorkspace = unsafe { GlobalWorkspace::new() };
+
+    with_global_workspace(&CHECKED_WORKSPACE, |buffer: &mut Vec<u32>| buffer.push(1));
+    let result = std::thread::spawn(|| {
+        with_global_workspace(&CHECKED_WORKSPACE, |buffer: &mut Vec<u32>| buffer.len())
+    })
+    .join();
+    assert!(result.is_err());
+    with_global_workspace(&CHECKED_WORKSPACE, |buffer: &mut Vec<u32>| {
+        assert_eq!(buffer, &[1])
+    });
+}
@@ -0,0 +1,165 @@
+//! Property-based test comparing a `Workspace` against a simple reference model under random
+//! sequences of operations.
+//!
+//! Random sequences freely mix keyed and unkeyed access to the same type, which `strict-tags`
+//! deliberately rejects, so the test is skipped with that feature.
+#![cfg(not(all(feature = "strict-tags", debug_assertions)))]
+
+use davenport::Workspace;
+use proptest::prelude::*;
+use std::any::TypeId;
+use std::collections::HashMap;
+
+const NUM_SLOTS: usize = 4;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Slot<const N: usize>(u64);
+
+struct Key;
+
+/// Identifies an object by its slot, and whether it is stored under `Key`.
+type ModelKey = (usize, bool);
+type Model = HashMap<ModelKey, u64>;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Add(usize, u64),
+    AddKeyed(usize, u64),
+    TryInsert(usize, u64),
+    Invalidate(usize),
+    Take(usize),
+    Touch(usize),
+    AddPair(usize, usize, u64),
+    Reorder(Vec<usize>),
+    Clear,
+}
+
+fn op_strategy() -> impl Strategy<Value = Op> {
+    let slot = 0..NUM_SLOTS;
+    prop_oneof![
+        4 => (slot.clone(), 1..100u64).prop_map(|(s, v)| Op::Add(s, v)),
+        2 => (slot.clone(), 1..100u64).prop_map(|(s, v)| Op::AddKeyed(s, v)),
+        2 => (slot.clone(), 1..100u64).prop_map(|(s, v)| Op::TryInsert(s, v)),
+        1 => slot.clone().prop_map(Op::Invalidate),
+        1 => slot.clone().prop_map(Op::Take),
+        1 => slot.clone().prop_map(Op::Touch),
+        1 => (slot.clone(), slot.clone(), 1..100u64).prop_map(|(a, b, v)| Op::AddPair(a, b, v)),
+        1 => Just((0..NUM_SLOTS).collect::<Vec<_>>())
+            .prop_shuffle()
+            .prop_map(Op::Reorder),
+        1 => Just(Op::Clear),
+    ]
+}
+
+fn type_id_of_slot(slot: usize) -> TypeId {
+    match slot {
+        0 => TypeId::of::<Slot<0>>(),
+        1 => TypeId::of::<Slot<1>>(),
+        2 => TypeId::of::<Slot<2>>(),
+        _ => TypeId::of::<Slot<3>>(),
+    }
+}
+
+fn apply<const N: usize>(ws: &mut Workspace, model: &mut Model, op: &Op) {
+    match *op {
+        Op::Add(_, v) => {
+            ws.get_or_default::<Slot<N>>().0 += v;
+            *model.entry((N, false)).or_default() += v;
+        }
+        Op::AddKeyed(_, v) => {
+            ws.get_keyed::<Key, Slot<N>>().0 += v;
+            *model.entry((N, true)).or_default() += v;
+        }
+        Op::TryInsert(_, v) => {
+            let inserted = ws.try_insert(Slot::<N>(v)).is_some();
+            assert_eq!(inserted, !model.contains_key(&(N, false)));
+            model.entry((N, false)).or_insert(v);
+        }
+        Op::Invalidate(_) => {
+            let removed = ws.invalidate::<Slot<N>>();
+            assert_eq!(removed, model.remove(&(N, false)).is_some());
+        }
+        Op::Take(_) => {
+            let taken = ws.take_or_default::<Slot<N>>();
+            assert_eq!(taken.0, model.remove(&(N, false)).unwrap_or(0));
+        }
+        Op::Touch(_) => {
+            assert_eq!(ws.touch::<Slot<N>>(), model.contains_key(&(N, false)));
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn apply_pair<const N: usize, const M: usize>(ws: &mut Workspace, model: &mut Model, v: u64) {
+    let (a, b) = ws.get_pair_mut::<Slot<N>, Slot<M>>();
+    a.0 += v;
+    b.0 += 2 * v;
+    *model.entry((N, false)).or_default() += v;
+    *model.entry((M, false)).or_default() += 2 * v;
+}
+
+fn dispatch(ws: &mut Workspace, model: &mut Model, op: &Op) {
+    match *op {
+        Op::Add(slot, _)
+        | Op::AddKeyed(slot, _)
+        | Op::TryInsert(slot, _)
+        | Op::Invalidate(slot)
+        | Op::Take(slot)
+        | Op::Touch(slot) => match slot {
+            0 => apply::<0>(ws, model, op),
+            1 => apply::<1>(ws, model, op),
+            2 => apply::<2>(ws, model, op),
+            _ => apply::<3>(ws, model, op),
+        },
+        Op::AddPair(a, b, v) => match (a, b) {
+            (0, 1) => apply_pair::<0, 1>(ws, model, v),
+            (1, 0) => apply_pair::<1, 0>(ws, model, v),
+            (2, 3) => apply_pair::<2, 3>(ws, model, v),
+            (3, 2) => apply_pair::<3, 2>(ws, model, v),
+            (0, 3) => apply_pair::<0, 3>(ws, model, v),
+            (3, 0) => apply_pair::<3, 0>(ws, model, v),
+            // Remaining combinations are either equal types or redundant for coverage
+            _ => {}
+        },
+        Op::Reorder(ref slots) => {
+            let order: Vec<_> = slots.iter().copied().map(type_id_of_slot).collect();
+            ws.set_order_for_testing(&order);
+        }
+        Op::Clear => {
+            ws.clear();
+            model.clear();
+        }
+    }
+}
+
+fn check<const N: usize>(ws: &Workspace, model: &Model) {
+    let unkeyed = model.get(&(N, false)).copied();
+    let keyed = model.get(&(N, true)).copied();
+    assert_eq!(ws.try_get::<Slot<N>>().map(|s| s.0), unkeyed);
+    assert_eq!(ws.try_get_keyed::<Key, Slot<N>>().map(|s| s.0), keyed);
+
+    let mut all: Vec<_> = ws.iter_of_type::<Slot<N>>().map(|s| s.0).collect();
+    let mut expected: Vec<_> = unkeyed.into_iter().chain(keyed).collect();
+    all.sort_unstable();
+    expected.sort_unstable();
+    assert_eq!(all, expected);
+}
+
+fn check_all(ws: &Workspace, model: &Model) {
+    check::<0>(ws, model);
+    check::<1>(ws, model);
+    check::<2>(ws, model);
+    check::<3>(ws, model);
+}
+
+proptest! {
+    #[test]
+    fn workspace_matches_reference_model(ops in prop::collection::vec(op_strategy(), 0..64)) {
+        let mut ws = Workspace::default();
+        let mut model = Model::new();
+        for op in &ops {
+            dispatch(&mut ws, &mut model, op);
+            check_all(&ws, &model);
+        }
+    }
+}
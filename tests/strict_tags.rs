@@ -0,0 +1,24 @@
+#![cfg(all(feature = "strict-tags", debug_assertions))]
+
+use davenport::Workspace;
+
+struct Key;
+
+#[test]
+#[should_panic(expected = "which is only stored under a key")]
+fn unkeyed_access_to_only_keyed_type_panics() {
+    let mut ws = Workspace::default();
+    ws.get_keyed::<Key, Vec<u8>>().push(1);
+    let _ = ws.get_or_default::<Vec<u8>>();
+}
+
+#[test]
+fn keyed_and_unkeyed_access_are_allowed_once_both_exist() {
+    struct OtherKey;
+
+    let mut ws = Workspace::default();
+    ws.get_or_default::<Vec<u8>>().push(1);
+    ws.get_keyed::<Key, Vec<u8>>().push(2);
+    ws.get_keyed::<OtherKey, Vec<u8>>().push(3);
+    assert_eq!(ws.get_or_default::<Vec<u8>>(), &[1]);
+}
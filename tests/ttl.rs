@@ -0,0 +1,31 @@
+#![cfg(feature = "ttl")]
+
+use davenport::Workspace;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn evict_older_than_removes_stale_entries_only() {
+    let start = Instant::now();
+    let elapsed = Rc::new(Cell::new(Duration::ZERO));
+    let mut ws = Workspace::default();
+    {
+        let elapsed = Rc::clone(&elapsed);
+        ws.set_clock(Box::new(move || start + elapsed.get()));
+    }
+
+    ws.get_or_default::<Vec<f64>>().push(1.0);
+    ws.get_or_default::<Vec<u32>>().push(2);
+    elapsed.set(Duration::from_secs(30));
+    ws.get_or_default::<Vec<u32>>().push(3);
+    elapsed.set(Duration::from_secs(45));
+
+    assert_eq!(ws.evict_older_than(Duration::from_secs(40)), 1);
+    assert!(ws.try_get::<Vec<f64>>().is_none());
+    assert_eq!(ws.try_get::<Vec<u32>>().unwrap(), &[2, 3]);
+
+    elapsed.set(Duration::from_secs(100));
+    assert_eq!(ws.evict_older_than(Duration::from_secs(40)), 1);
+    assert!(ws.try_get::<Vec<u32>>().is_none());
+}
@@ -1,4 +1,27 @@
-use davenport::{define_thread_local_workspace, with_thread_local_workspace, Workspace};
+use std::any::TypeId;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::thread::LocalKey;
+
+use davenport::{
+    assert_workspace_types, catch_workspace, debug_assert_workspace_free,
+    define_thread_local_reentrant_workspace, define_thread_local_workspace,
+    is_thread_local_workspace_borrowed, scope_with_borrowed_workspace, scoped_set_workspace_value,
+    thread_local_workspace_iter, try_with_thread_local_workspace,
+    with_fresh_thread_local_workspace, with_thread_local_capped_slice,
+    with_thread_local_reentrant_workspace, with_thread_local_workspace,
+    with_thread_local_workspace_async_safe, with_thread_local_workspace_controlled,
+    with_thread_local_workspace_ctx, with_thread_local_workspace_pair,
+    with_thread_local_workspace_raw, with_thread_local_workspace_take,
+    with_thread_local_workspace_tracked, with_thread_local_workspace_with_policy,
+    with_workspace_field, AfterUse, AlignedScratch, AllocEvent, BoundedWorkspace, BuildFromContext,
+    DirtyTracked, EnumWorkspace, LayeredWorkspace, MemoryPolicy, Poison, PrewarmSpec,
+    RecursionGuard, RecyclingVecOfVecs, ReentrantWorkspace, ScratchAllocator, ScratchMap,
+    SharedCapacityPool, SharedWorkspace, SharedWorkspaceError, Teardown, TraitSlot, Workspace,
+    WorkspaceBuilder, WorkspaceBusy, WorkspaceError, WorkspaceKey, WorkspaceOp, WorkspacePool,
+    WorkspaceReserve,
+};
 
 #[derive(Default)]
 struct A(usize);
@@ -98,3 +121,2548 @@ fn with_thread_local_workspace_consistency() {
     });
     assert_eq!(retval, 2);
 }
+
+#[test]
+fn layered_workspace_reuses_parent_and_creates_local() {
+    let mut parent = Workspace::default();
+    parent.get_or_default::<A>().0 = 4;
+
+    {
+        let mut child = LayeredWorkspace::new(Some(&mut parent));
+        // A is found in the parent, so the parent's object is reused
+        let a: &mut A = child.get_or_default();
+        assert_eq!(a.0, 4);
+        a.0 = 5;
+
+        // B is in neither, so it is created in the local workspace
+        let b: &mut B = child.get_or_default();
+        assert_eq!(b.0, 0);
+        b.0 = 6;
+        assert_eq!(child.local().try_get::<B>().unwrap().0, 6);
+        assert!(child.local().try_get::<A>().is_none());
+    }
+
+    assert_eq!(parent.try_get::<A>().unwrap().0, 5);
+    assert!(parent.try_get::<B>().is_none());
+}
+
+#[test]
+fn layered_workspace_lookup_leaves_parent_order_and_budget_untouched() {
+    let mut parent = Workspace::default();
+    parent.register_memory_usage::<Vec<u8>>();
+    parent.register_memory_usage::<Vec<u16>>();
+    parent.get_or_default::<Vec<u8>>().reserve_exact(100);
+    parent.get_or_default::<Vec<u16>>().reserve_exact(100);
+    parent.set_memory_budget(Some(100));
+    let accesses = Rc::new(Cell::new(0));
+    let counted = Rc::clone(&accesses);
+    parent.set_observer(Box::new(move |_, _| counted.set(counted.get() + 1)));
+
+    {
+        let mut child = LayeredWorkspace::new(Some(&mut parent));
+        assert!(child.get_or_default::<Vec<u8>>().capacity() >= 100);
+        assert!(child.get_or_default::<Vec<u16>>().capacity() >= 100);
+    }
+
+    assert_eq!(accesses.get(), 0);
+    assert_eq!(parent.peek_lru_type_id(), Some(TypeId::of::<Vec<u8>>()));
+    assert_eq!(parent.memory_usage(), 300);
+}
+
+#[test]
+fn workspace_try_insert_or_returns_rejected_value() {
+    let mut ws = Workspace::default();
+    let (a, rejected) = ws.try_insert_or(A(3));
+    assert_eq!(a.0, 3);
+    assert!(rejected.is_none());
+
+    let (a, rejected) = ws.try_insert_or(A(5));
+    assert_eq!(a.0, 3);
+    assert_eq!(rejected.unwrap().0, 5);
+    assert_eq!(ws.get_or_default::<A>().0, 3);
+}
+
+#[test]
+fn workspace_observer_records_access_sequence() {
+    let accesses = Rc::new(RefCell::new(Vec::new()));
+    let mut ws = Workspace::default();
+    {
+        let accesses = Rc::clone(&accesses);
+        ws.set_observer(Box::new(move |id, hit| {
+            accesses.borrow_mut().push((id, hit))
+        }));
+    }
+
+    let _ = ws.get_or_default::<A>();
+    let _ = ws.get_or_default::<B>();
+    let _ = ws.get_or_default::<A>();
+    let _ = ws.get_or_insert_with(|| B(1));
+
+    let a = TypeId::of::<A>();
+    let b = TypeId::of::<B>();
+    assert_eq!(
+        *accesses.borrow(),
+        vec![(a, false), (b, false), (a, true), (b, true)]
+    );
+}
+
+#[test]
+fn workspace_extract_if() {
+    let mut ws = Workspace::default();
+    ws.get_or_default::<A>().0 = 1;
+    ws.get_or_default::<B>().0 = 2;
+    ws.get_or_default::<Vec<u32>>().push(3);
+
+    let extracted = ws.extract_if(|id| id == TypeId::of::<B>());
+    assert_eq!(extracted.len(), 1);
+    let b = extracted
+        .into_iter()
+        .next()
+        .unwrap()
+        .downcast::<B>()
+        .unwrap();
+    assert_eq!(b.0, 2);
+
+    assert!(ws.try_get::<B>().is_none());
+    assert_eq!(ws.try_get::<A>().unwrap().0, 1);
+    assert_eq!(ws.try_get::<Vec<u32>>().unwrap(), &vec![3]);
+}
+
+define_thread_local_workspace!(CATCH_WORKSPACE);
+
+#[test]
+fn catch_workspace_recovers_from_reentry_panic() {
+    let result = catch_workspace(|| {
+        with_thread_local_workspace(&CATCH_WORKSPACE, |a: &mut A| {
+            a.0 = 1;
+            with_thread_local_workspace(&CATCH_WORKSPACE, |b: &mut B| b.0)
+        })
+    });
+    let err = result.unwrap_err();
+    assert!(err.message().unwrap().contains("recursively"));
+
+    // The workspace is usable again after catching the panic
+    let value = catch_workspace(|| with_thread_local_workspace(&CATCH_WORKSPACE, |a: &mut A| a.0));
+    assert_eq!(value.unwrap(), 1);
+}
+
+trait MyBuffer {
+    fn reset(&mut self);
+    fn name(&self) -> &'static str;
+}
+
+#[derive(Default)]
+struct IntBuffer(Vec<i32>);
+
+#[derive(Default)]
+struct FloatBuffer(Vec<f64>);
+
+impl MyBuffer for IntBuffer {
+    fn reset(&mut self) {
+        self.0.clear();
+    }
+
+    fn name(&self) -> &'static str {
+        "int"
+    }
+}
+
+impl MyBuffer for FloatBuffer {
+    fn reset(&mut self) {
+        self.0.clear();
+    }
+
+    fn name(&self) -> &'static str {
+        "float"
+    }
+}
+
+#[test]
+fn workspace_for_each_as_visits_registered_types() {
+    let mut ws = Workspace::default();
+    ws.register_as::<IntBuffer, dyn MyBuffer>(|b| b);
+    ws.register_as::<FloatBuffer, dyn MyBuffer>(|b| b);
+    ws.get_or_default::<IntBuffer>().0.push(1);
+    ws.get_or_default::<FloatBuffer>().0.push(2.0);
+    // A is not registered and must not be visited
+    ws.get_or_default::<A>().0 = 3;
+
+    let mut visited = Vec::new();
+    ws.for_each_as::<dyn MyBuffer>(|buffer| {
+        visited.push(buffer.name());
+        buffer.reset();
+    });
+    visited.sort_unstable();
+
+    assert_eq!(visited, vec!["float", "int"]);
+    assert!(ws.try_get::<IntBuffer>().unwrap().0.is_empty());
+    assert!(ws.try_get::<FloatBuffer>().unwrap().0.is_empty());
+    assert_eq!(ws.try_get::<A>().unwrap().0, 3);
+}
+
+#[derive(Default)]
+struct Nested<T> {
+    inner: Vec<T>,
+    pair: (T, T),
+}
+
+#[test]
+fn workspace_composite_types_are_reused() {
+    fn check_reuse<W: 'static + Default + PartialEq + std::fmt::Debug>(
+        ws: &mut Workspace,
+        modify: impl Fn(&mut W),
+    ) {
+        let w: &mut W = ws.get_or_default();
+        assert_eq!(*w, W::default());
+        modify(w);
+        let expected = {
+            let mut expected = W::default();
+            modify(&mut expected);
+            expected
+        };
+        assert_eq!(*ws.get_or_default::<W>(), expected);
+    }
+
+    let mut ws = Workspace::default();
+    check_reuse::<(Vec<u32>, Vec<u32>)>(&mut ws, |(a, b)| {
+        a.push(1);
+        b.push(2);
+    });
+    check_reuse::<(u32, f64, Vec<u8>)>(&mut ws, |t| t.2.push(7));
+    check_reuse::<[u64; 4]>(&mut ws, |arr| arr[2] = 5);
+    check_reuse::<[u32; 4]>(&mut ws, |arr| arr[1] = 6);
+    check_reuse::<Option<Vec<u8>>>(&mut ws, |opt| *opt = Some(vec![1, 2]));
+    check_reuse::<Option<Vec<u16>>>(&mut ws, |opt| *opt = Some(vec![3]));
+
+    // Nested generic structs with different type arguments must not alias
+    ws.get_or_default::<Nested<u32>>().inner.push(1);
+    ws.get_or_default::<Nested<u64>>().pair = (2, 3);
+
+    // Each type still holds its state after all the other accesses
+    assert_eq!(ws.get_or_default::<(Vec<u32>, Vec<u32>)>().1, vec![2]);
+    assert_eq!(ws.get_or_default::<(u32, f64, Vec<u8>)>().2, vec![7]);
+    assert_eq!(*ws.get_or_default::<[u64; 4]>(), [0, 0, 5, 0]);
+    assert_eq!(*ws.get_or_default::<[u32; 4]>(), [0, 6, 0, 0]);
+    assert_eq!(*ws.get_or_default::<Option<Vec<u8>>>(), Some(vec![1, 2]));
+    assert_eq!(*ws.get_or_default::<Option<Vec<u16>>>(), Some(vec![3]));
+    let nested_u32 = ws.get_or_default::<Nested<u32>>();
+    assert_eq!(nested_u32.inner, vec![1]);
+    assert_eq!(nested_u32.pair, (0, 0));
+    let nested_u64 = ws.get_or_default::<Nested<u64>>();
+    assert!(nested_u64.inner.is_empty());
+    assert_eq!(nested_u64.pair, (2, 3));
+}
+
+define_thread_local_workspace!(COMPOSITE_WORKSPACE);
+
+#[test]
+fn with_thread_local_workspace_composite_types() {
+    with_thread_local_workspace(&COMPOSITE_WORKSPACE, |pair: &mut (Vec<u32>, Vec<u32>)| {
+        pair.0.push(1);
+        pair.1.push(2);
+    });
+    with_thread_local_workspace(&COMPOSITE_WORKSPACE, |arr: &mut [u8; 3]| arr[0] = 9);
+    with_thread_local_workspace(&COMPOSITE_WORKSPACE, |pair: &mut (Vec<u32>, Vec<u32>)| {
+        assert_eq!(pair, &(vec![1], vec![2]));
+    });
+    with_thread_local_workspace(&COMPOSITE_WORKSPACE, |arr: &mut [u8; 3]| {
+        assert_eq!(arr, &[9, 0, 0]);
+    });
+}
+
+#[test]
+fn workspace_const_constructor() {
+    const EMPTY: Workspace = Workspace::new();
+    thread_local! {
+        static CONST_INITIALIZED: RefCell<Workspace> = const { RefCell::new(Workspace::new()) };
+    }
+
+    let mut ws = EMPTY;
+    assert!(ws.try_get::<A>().is_none());
+    ws.get_or_default::<A>().0 = 1;
+    assert_eq!(ws.try_get::<A>().unwrap().0, 1);
+
+    let value = with_thread_local_workspace(&CONST_INITIALIZED, |a: &mut A| {
+        a.0 += 1;
+        a.0
+    });
+    assert_eq!(value, 1);
+}
+
+#[test]
+fn aligned_scratch_alignment_is_preserved_across_reuse() {
+    let mut ws = Workspace::default();
+    for &len in &[0, 3, 17, 5, 100, 1, 1000] {
+        let scratch: &mut AlignedScratch<f32, 64> = ws.get_or_default();
+        let buffer = scratch.prepare(len);
+        assert_eq!(buffer.len(), len);
+        assert_eq!(buffer.as_ptr() as usize % 64, 0);
+        buffer.iter_mut().for_each(|x| *x = 1.0);
+    }
+
+    // Existing elements are preserved and new elements are defaulted
+    let scratch: &mut AlignedScratch<f32, 64> = ws.get_or_default();
+    let capacity = scratch.capacity();
+    let buffer = scratch.prepare(2);
+    assert_eq!(buffer, &[1.0, 1.0]);
+    let buffer = scratch.prepare(4);
+    assert_eq!(buffer, &[1.0, 1.0, 0.0, 0.0]);
+    assert_eq!(scratch.capacity(), capacity);
+
+    let mut scratch = AlignedScratch::<u8, 4096>::new();
+    assert_eq!(scratch.prepare(10).as_ptr() as usize % 4096, 0);
+    assert_eq!(scratch.prepare(10000).as_ptr() as usize % 4096, 0);
+}
+
+#[test]
+fn workspace_clone_type() {
+    let mut ws = Workspace::default();
+    assert!(ws.clone_type::<Vec<u32>>().is_none());
+
+    ws.get_or_default::<Vec<u32>>().extend([1, 2, 3]);
+    let mut snapshot = ws.clone_type::<Vec<u32>>().unwrap();
+    snapshot.push(4);
+
+    assert_eq!(snapshot, vec![1, 2, 3, 4]);
+    assert_eq!(ws.try_get::<Vec<u32>>().unwrap(), &vec![1, 2, 3]);
+}
+
+#[test]
+fn workspace_high_water_mark() {
+    let mut ws = Workspace::default();
+    assert!(ws.high_water_mark::<Vec<u32>>().is_none());
+
+    // Untracked objects have no high-water mark
+    let _ = ws.get_or_default::<Vec<u8>>();
+    assert!(ws.high_water_mark::<Vec<u8>>().is_none());
+
+    let buffer: &mut Vec<u32> = ws.get_or_default_tracked();
+    buffer.reserve_exact(100);
+    let peak = buffer.capacity();
+    assert!(peak >= 100);
+    assert_eq!(ws.high_water_mark::<Vec<u32>>(), Some(peak));
+
+    // Access through the untracked accessor still records the capacity
+    let buffer: &mut Vec<u32> = ws.get_or_default();
+    buffer.shrink_to_fit();
+    assert_eq!(buffer.capacity(), 0);
+    let _ = ws.get_or_default::<Vec<u32>>();
+    assert_eq!(ws.high_water_mark::<Vec<u32>>(), Some(peak));
+
+    let buffer: &mut Vec<u32> = ws.get_or_default_tracked();
+    buffer.reserve_exact(10);
+    assert_eq!(ws.high_water_mark::<Vec<u32>>(), Some(peak));
+}
+
+define_thread_local_workspace!(GUARDED_WORKSPACE);
+
+#[test]
+#[cfg(debug_assertions)]
+fn debug_assert_workspace_free_fires_when_held() {
+    debug_assert_workspace_free!(GUARDED_WORKSPACE);
+
+    let result = catch_workspace(|| {
+        with_thread_local_workspace(&GUARDED_WORKSPACE, |_: &mut A| {
+            debug_assert_workspace_free!(GUARDED_WORKSPACE);
+        })
+    });
+    assert!(result
+        .unwrap_err()
+        .message()
+        .unwrap()
+        .contains("currently in use"));
+
+    debug_assert_workspace_free!(GUARDED_WORKSPACE);
+}
+
+#[test]
+fn recursion_guard() {
+    thread_local! { static IN_REGION: Cell<bool> = const { Cell::new(false) }; }
+
+    {
+        let _guard = RecursionGuard::enter(&IN_REGION);
+        assert!(RecursionGuard::try_enter(&IN_REGION).is_none());
+        let result = catch_workspace(|| RecursionGuard::enter(&IN_REGION));
+        assert!(result.is_err());
+    }
+
+    // The region can be entered again once the guard has been dropped
+    assert!(RecursionGuard::try_enter(&IN_REGION).is_some());
+}
+
+#[test]
+fn workspace_handle_repeated_access() {
+    let mut ws = Workspace::default();
+    let _ = ws.get_or_default::<B>();
+    let handle = ws.handle::<A>();
+    for i in 0..10 {
+        let a = handle.get(&mut ws).unwrap();
+        assert_eq!(a.0, i);
+        a.0 += 1;
+    }
+    assert_eq!(ws.try_get::<A>().unwrap().0, 10);
+}
+
+#[test]
+fn workspace_handle_invalidation() {
+    let mut ws = Workspace::default();
+    let _ = ws.get_or_default::<B>();
+    let handle = ws.handle::<A>();
+    assert!(handle.get(&mut ws).is_some());
+
+    // Accessing B swaps it into the most-recently-used position previously held by A
+    let _ = ws.get_or_default::<B>();
+    assert!(handle.get(&mut ws).is_none());
+
+    let handle = ws.handle::<A>();
+    assert!(handle.get(&mut ws).is_some());
+    ws.clear();
+    assert!(handle.get(&mut ws).is_none());
+}
+
+#[test]
+fn workspace_capacities_snapshot_round_trip() {
+    let mut ws = Workspace::default();
+    ws.get_or_default_tracked::<Vec<u32>>().reserve_exact(100);
+    ws.get_or_default_tracked::<Vec<f64>>().reserve_exact(50);
+    // Untracked objects are not part of the snapshot
+    ws.get_or_default::<Vec<u8>>().reserve_exact(10);
+
+    let mut snapshot = ws.capacities_snapshot();
+    snapshot.sort_unstable_by_key(|&(_, capacity)| capacity);
+    assert_eq!(
+        snapshot,
+        vec![
+            (TypeId::of::<Vec<f64>>(), 50),
+            (TypeId::of::<Vec<u32>>(), 100)
+        ]
+    );
+
+    let mut restored = WorkspaceBuilder::new()
+        .register::<Vec<u32>>()
+        .register::<Vec<f64>>()
+        .build(&snapshot);
+    assert!(restored.try_get::<Vec<u8>>().is_none());
+    assert!(restored.try_get::<Vec<u32>>().unwrap().capacity() >= 100);
+    assert!(restored.try_get::<Vec<f64>>().unwrap().capacity() >= 50);
+    assert_eq!(
+        restored.high_water_mark::<Vec<u32>>(),
+        ws.high_water_mark::<Vec<u32>>()
+    );
+    assert!(restored.get_or_default::<Vec<u32>>().is_empty());
+
+    // Types that are not registered are skipped
+    let partial = WorkspaceBuilder::new()
+        .register::<Vec<f64>>()
+        .build(&snapshot);
+    assert!(partial.try_get::<Vec<u32>>().is_none());
+    assert!(partial.try_get::<Vec<f64>>().is_some());
+}
+
+define_thread_local_workspace!(POLICY_WORKSPACE);
+
+#[test]
+fn memory_policy_grow_only_never_shrinks() {
+    with_thread_local_workspace_with_policy(
+        &POLICY_WORKSPACE,
+        MemoryPolicy::GrowOnly,
+        |buffer: &mut Vec<u32>| buffer.resize(1000, 0),
+    );
+    with_thread_local_workspace(&POLICY_WORKSPACE, |buffer: &mut Vec<u32>| {
+        assert!(buffer.capacity() >= 1000);
+        buffer.clear();
+    });
+    with_thread_local_workspace(&POLICY_WORKSPACE, |buffer: &mut Vec<u32>| {
+        assert!(buffer.capacity() >= 1000);
+    });
+}
+
+#[test]
+fn memory_policy_shrink_after_use() {
+    let policy = MemoryPolicy::ShrinkAfterUse { keep_ratio: 2.0 };
+    with_thread_local_workspace_with_policy(&POLICY_WORKSPACE, policy, |buffer: &mut Vec<u8>| {
+        buffer.resize(1000, 0)
+    });
+    with_thread_local_workspace(&POLICY_WORKSPACE, |buffer: &mut Vec<u8>| {
+        // Capacity is within the ratio, so no shrinking took place
+        assert!(buffer.capacity() >= 1000);
+        buffer.truncate(10);
+    });
+    with_thread_local_workspace(&POLICY_WORKSPACE, |buffer: &mut Vec<u8>| {
+        assert_eq!(buffer.len(), 10);
+        assert!(buffer.capacity() <= 20);
+    });
+
+    // The policy can also be applied explicitly
+    let mut ws = Workspace::default();
+    ws.get_or_default_with_policy::<String>(policy)
+        .push_str("abc");
+    ws.get_or_default::<String>().reserve(1000);
+    ws.apply_memory_policy::<String>();
+    assert!(ws.get_or_default::<String>().capacity() <= 6);
+}
+
+struct WrappedKey(&'static LocalKey<RefCell<Workspace>>);
+
+impl WorkspaceKey for WrappedKey {
+    fn with_cell<T>(&self, f: impl FnOnce(&RefCell<Workspace>) -> T) -> T {
+        self.0.with(f)
+    }
+}
+
+define_thread_local_workspace!(WRAPPED_WORKSPACE);
+
+#[test]
+fn with_thread_local_workspace_accepts_wrapped_key() {
+    fn generic_use<K: WorkspaceKey>(key: K, value: usize) -> usize {
+        with_thread_local_workspace(key, |a: &mut A| {
+            a.0 += value;
+            a.0
+        })
+    }
+
+    assert_eq!(generic_use(WrappedKey(&WRAPPED_WORKSPACE), 2), 2);
+    assert_eq!(generic_use(&WRAPPED_WORKSPACE, 3), 5);
+}
+
+#[test]
+fn scratch_map_fresh_retains_capacity() {
+    let mut ws = Workspace::default();
+    let scratch: &mut ScratchMap<u32, Vec<u32>> = ws.get_or_default();
+    let map = scratch.fresh();
+    for i in 0..100 {
+        map.entry(i % 10).or_default().push(i);
+    }
+    assert_eq!(map.len(), 10);
+    let capacity = scratch.capacity();
+    assert!(capacity >= 10);
+
+    let scratch: &mut ScratchMap<u32, Vec<u32>> = ws.get_or_default();
+    assert_eq!(scratch.get().len(), 10);
+    let map = scratch.fresh();
+    assert!(map.is_empty());
+    map.insert(1, vec![1]);
+    assert_eq!(scratch.capacity(), capacity);
+    assert_eq!(scratch.get().len(), 1);
+}
+
+#[test]
+fn workspace_get_slice_or_default() {
+    trait Producer<T> {
+        fn num_elements(&self) -> usize;
+        fn populate_buffer(&self, buffer: &mut [T]);
+    }
+
+    struct Range(u32);
+
+    impl Producer<u32> for Range {
+        fn num_elements(&self) -> usize {
+            self.0 as usize
+        }
+
+        fn populate_buffer(&self, buffer: &mut [u32]) {
+            buffer.iter_mut().zip(0..).for_each(|(x, i)| *x = i);
+        }
+    }
+
+    fn compute_sum<T>(ws: &mut Workspace, producer: &dyn Producer<T>) -> T
+    where
+        T: 'static + Default + Copy + std::iter::Sum,
+    {
+        let buffer = ws.get_slice_or_default(producer.num_elements());
+        producer.populate_buffer(buffer);
+        buffer.iter().copied().sum()
+    }
+
+    let mut ws = Workspace::default();
+    assert_eq!(compute_sum(&mut ws, &Range(5)), 10);
+    let capacity = ws.try_get::<Vec<u32>>().unwrap().capacity();
+    assert_eq!(compute_sum(&mut ws, &Range(3)), 3);
+    assert_eq!(ws.get_slice_or_default::<u32>(3).len(), 3);
+    assert_eq!(ws.try_get::<Vec<u32>>().unwrap().capacity(), capacity);
+}
+
+#[test]
+fn workspace_memory_budget_evicts_least_recently_used_first() {
+    let mut ws = Workspace::default();
+    ws.register_memory_usage::<Vec<u8>>();
+    ws.register_memory_usage::<Vec<u16>>();
+    ws.register_memory_usage::<Vec<u32>>();
+    ws.get_or_default::<Vec<u8>>().reserve_exact(100);
+    ws.get_or_default::<Vec<u16>>().reserve_exact(50);
+    ws.get_or_default::<Vec<u32>>().reserve_exact(25);
+    // Vec<u16> is now the least recently used object, followed by Vec<u32>
+    let _ = ws.get_or_default::<Vec<u8>>();
+    assert_eq!(
+        ws.entries_idle_for(1),
+        [TypeId::of::<Vec<u16>>(), TypeId::of::<Vec<u32>>()]
+    );
+
+    ws.set_memory_budget(Some(200));
+    let _ = ws.get_or_default::<Vec<u8>>();
+    assert!(ws.try_get::<Vec<u16>>().is_none());
+    assert!(ws.try_get::<Vec<u32>>().is_some());
+}
+
+#[test]
+fn workspace_memory_budget_evicts_coldest() {
+    let mut ws = Workspace::default();
+    ws.register_memory_usage::<Vec<u8>>();
+    ws.register_memory_usage::<Vec<u16>>();
+    ws.register_memory_usage::<Vec<u32>>();
+    ws.set_memory_budget(Some(250));
+
+    ws.get_or_default::<Vec<u8>>().reserve_exact(100);
+    ws.get_or_default::<Vec<u16>>().reserve_exact(50);
+    // Unregistered types are never evicted
+    ws.get_or_default::<A>().0 = 1;
+    // Memory usage is accounted on access, so the new allocation is noticed here
+    let _ = ws.get_or_default::<Vec<u32>>();
+    ws.get_or_default::<Vec<u32>>().reserve_exact(25);
+    assert_eq!(ws.memory_usage(), 300);
+
+    // Accessing A triggers enforcement of the budget, evicting the coldest Vec<u8>
+    let _ = ws.get_or_default::<A>();
+    assert!(ws.try_get::<Vec<u8>>().is_none());
+    assert!(ws.try_get::<Vec<u16>>().is_some());
+    assert!(ws.try_get::<Vec<u32>>().is_some());
+    assert_eq!(ws.try_get::<A>().unwrap().0, 1);
+    assert_eq!(ws.memory_usage(), 200);
+
+    // An object larger than the budget is retained while it is being accessed
+    ws.get_or_default::<Vec<u8>>().reserve_exact(1000);
+    let _ = ws.get_or_default::<Vec<u8>>();
+    assert!(ws.try_get::<Vec<u8>>().is_some());
+    assert!(ws.try_get::<Vec<u16>>().is_none());
+    assert!(ws.try_get::<Vec<u32>>().is_none());
+}
+
+#[test]
+// Creates an unkeyed object after keyed ones of the same type, which `strict-tags` rejects
+#[cfg(not(all(feature = "strict-tags", debug_assertions)))]
+fn workspace_keyed_slots_are_independent() {
+    struct ModuleAKey;
+    struct ModuleBKey;
+
+    let mut ws = Workspace::default();
+    ws.get_keyed::<ModuleAKey, Vec<u8>>().push(1);
+    ws.get_keyed::<ModuleBKey, Vec<u8>>().push(2);
+    ws.get_or_default::<Vec<u8>>().push(3);
+
+    assert_eq!(ws.get_keyed::<ModuleAKey, Vec<u8>>(), &[1]);
+    assert_eq!(ws.get_keyed::<ModuleBKey, Vec<u8>>(), &[2]);
+    assert_eq!(ws.get_or_default::<Vec<u8>>(), &[3]);
+    assert_eq!(ws.try_get_keyed::<ModuleAKey, Vec<u8>>().unwrap(), &[1]);
+    assert!(ws.try_get_keyed::<ModuleAKey, Vec<u16>>().is_none());
+}
+
+define_thread_local_workspace!(CTX_WORKSPACE);
+
+#[test]
+fn with_thread_local_workspace_ctx_passes_context() {
+    struct Context<'a> {
+        values: &'a [u32],
+        offset: u32,
+    }
+
+    fn shifted_sum(values: &[u32], offset: u32) -> u32 {
+        let ctx = Context { values, offset };
+        with_thread_local_workspace_ctx(&CTX_WORKSPACE, ctx, |buffer: &mut Vec<u32>, ctx| {
+            buffer.clear();
+            buffer.extend(ctx.values.iter().map(|v| v + ctx.offset));
+            buffer.iter().sum()
+        })
+    }
+
+    assert_eq!(shifted_sum(&[1, 2, 3], 1), 9);
+    assert_eq!(shifted_sum(&[4], 2), 6);
+}
+
+#[test]
+fn workspace_touch_promotes_to_mru() {
+    let mut ws = Workspace::default();
+    assert!(!ws.touch::<A>());
+    assert!(ws.try_get::<A>().is_none());
+
+    ws.get_or_default::<A>().0 = 1;
+    // B is now in the most-recently-used position
+    let b_handle = ws.handle::<B>();
+    assert!(b_handle.get(&mut ws).is_some());
+
+    assert!(ws.touch::<A>());
+    // A took B's position, so B's handle is invalidated while a handle to A at that
+    // position is obtained without any reordering
+    assert!(b_handle.get(&mut ws).is_none());
+    let a_handle = ws.handle::<A>();
+    assert!(ws.touch::<A>());
+    assert_eq!(a_handle.get(&mut ws).unwrap().0, 1);
+    assert!(ws.try_get::<B>().is_some());
+}
+
+#[test]
+fn workspace_pool_simultaneous_checkout() {
+    let pool = WorkspacePool::new();
+    {
+        let mut first = pool.checkout::<Vec<u8>>();
+        let mut second = pool.checkout::<Vec<u8>>();
+        first.push(1);
+        second.push(2);
+        assert_eq!(pool.available::<Vec<u8>>(), 0);
+    }
+    assert_eq!(pool.available::<Vec<u8>>(), 2);
+
+    // Returned objects are reused rather than created anew
+    let mut contents: Vec<Vec<u8>> = {
+        let first = pool.checkout::<Vec<u8>>();
+        let second = pool.checkout::<Vec<u8>>();
+        vec![first.clone(), second.clone()]
+    };
+    contents.sort();
+    assert_eq!(contents, vec![vec![1], vec![2]]);
+
+    // Removing an object from the pool means it is not returned
+    let owned = pool.checkout::<Vec<u8>>().into_inner();
+    assert_eq!(owned.len(), 1);
+    assert_eq!(pool.available::<Vec<u8>>(), 1);
+}
+
+#[test]
+fn workspace_panicking_constructor_leaves_workspace_intact() {
+    struct PanicsOnDefault;
+
+    impl Default for PanicsOnDefault {
+        fn default() -> Self {
+            panic!("Default panicked")
+        }
+    }
+
+    let mut ws = Workspace::default();
+    ws.get_or_default::<A>().0 = 1;
+
+    let result = catch_workspace(|| {
+        let _ = ws.get_or_default::<PanicsOnDefault>();
+    });
+    assert_eq!(result.unwrap_err().message(), Some("Default panicked"));
+    let result = catch_workspace(|| {
+        let _ = ws.get_or_insert_with::<B, _>(|| panic!("closure panicked"));
+    });
+    assert_eq!(result.unwrap_err().message(), Some("closure panicked"));
+
+    assert!(ws.try_get::<PanicsOnDefault>().is_none());
+    assert!(ws.try_get::<B>().is_none());
+    assert_eq!(ws.try_get::<A>().unwrap().0, 1);
+    assert_eq!(ws.get_or_insert_with(|| B(2)).0, 2);
+    assert_eq!(ws.get_or_default::<A>().0, 1);
+}
+
+#[test]
+// Creates an unkeyed object after keyed ones of the same type, which `strict-tags` rejects
+#[cfg(not(all(feature = "strict-tags", debug_assertions)))]
+fn workspace_iter_of_type_includes_keyed() {
+    struct Key1;
+    struct Key2;
+    struct Key3;
+
+    let mut ws = Workspace::default();
+    assert_eq!(ws.iter_of_type::<Vec<f64>>().count(), 0);
+
+    ws.get_keyed::<Key1, Vec<f64>>().push(1.0);
+    ws.get_keyed::<Key2, Vec<f64>>().extend([1.0, 2.0]);
+    ws.get_keyed::<Key3, Vec<f64>>().extend([1.0, 2.0, 3.0]);
+    ws.get_keyed::<Key1, Vec<u32>>().push(1);
+    let _ = ws.get_or_default::<A>();
+
+    let mut lengths: Vec<_> = ws.iter_of_type::<Vec<f64>>().map(Vec::len).collect();
+    lengths.sort_unstable();
+    assert_eq!(lengths, vec![1, 2, 3]);
+
+    ws.get_or_default::<Vec<f64>>().extend([0.0; 4]);
+    let total: usize = ws.iter_of_type::<Vec<f64>>().map(Vec::len).sum();
+    assert_eq!(total, 10);
+    assert_eq!(ws.iter_of_type::<A>().count(), 1);
+}
+
+#[test]
+fn workspace_keyed_and_unkeyed_namespaces_are_separate() {
+    let mut ws = Workspace::default();
+
+    // Unkeyed objects are not visible through any key, including the unit key
+    ws.get_or_default::<Vec<u8>>().push(1);
+    assert!(ws.try_get_keyed::<(), Vec<u8>>().is_none());
+    assert!(ws.get_keyed::<(), Vec<u8>>().is_empty());
+
+    // Keyed objects are not visible through unkeyed access
+    ws.get_keyed::<(), Vec<u8>>().push(2);
+    ws.get_keyed::<u8, Vec<u8>>().push(3);
+    assert_eq!(ws.try_get::<Vec<u8>>().unwrap(), &[1]);
+    assert_eq!(ws.get_or_default::<Vec<u8>>(), &[1]);
+    assert_eq!(ws.get_keyed::<(), Vec<u8>>(), &[2]);
+
+    // Clearing removes both kinds of objects
+    ws.clear();
+    assert!(ws.try_get::<Vec<u8>>().is_none());
+    assert!(ws.try_get_keyed::<(), Vec<u8>>().is_none());
+    assert!(ws.try_get_keyed::<u8, Vec<u8>>().is_none());
+}
+
+#[test]
+fn workspace_get_or_compute_runs_until_invalidated() {
+    struct Derived(u32);
+
+    let computations = Cell::new(0);
+    let compute = || {
+        computations.set(computations.get() + 1);
+        Derived(42)
+    };
+
+    let mut ws = Workspace::default();
+    assert_eq!(ws.get_or_compute(compute).0, 42);
+    assert_eq!(ws.get_or_compute(compute).0, 42);
+    assert_eq!(computations.get(), 1);
+
+    assert!(ws.invalidate::<Derived>());
+    assert!(!ws.invalidate::<Derived>());
+    assert_eq!(ws.get_or_compute(compute).0, 42);
+    assert_eq!(computations.get(), 2);
+}
+
+#[test]
+fn workspace_get_boxed_slice_or_default_reuses_same_length() {
+    let mut ws = Workspace::default();
+    let buffer = ws.get_boxed_slice_or_default::<u64>(4);
+    assert_eq!(buffer, &[0; 4]);
+    buffer.fill(9);
+    let ptr = buffer.as_ptr();
+
+    let buffer = ws.get_boxed_slice_or_default::<u64>(4);
+    assert_eq!(buffer.as_ptr(), ptr);
+    assert_eq!(buffer, &[9; 4]);
+}
+
+#[test]
+fn workspace_get_boxed_slice_or_default_reallocates_on_length_change() {
+    let mut ws = Workspace::default();
+    ws.get_boxed_slice_or_default::<u64>(4).fill(9);
+
+    let buffer = ws.get_boxed_slice_or_default::<u64>(2);
+    assert_eq!(buffer, &[0; 2]);
+    buffer.fill(5);
+    assert_eq!(ws.get_boxed_slice_or_default::<u64>(6), &[0; 6]);
+    assert_eq!(ws.try_get::<Box<[u64]>>().unwrap().len(), 6);
+    assert!(ws.try_get::<Vec<u64>>().is_none());
+}
+
+#[test]
+fn workspace_for_each_registered_shares_registrations_between_ops() {
+    struct RecordNames(Vec<&'static str>);
+
+    impl WorkspaceOp for RecordNames {
+        type Target = dyn MyBuffer;
+
+        fn apply(&mut self, target: &mut Self::Target) {
+            self.0.push(target.name());
+        }
+    }
+
+    struct Reset(usize);
+
+    impl WorkspaceOp for Reset {
+        type Target = dyn MyBuffer;
+
+        fn apply(&mut self, target: &mut Self::Target) {
+            target.reset();
+            self.0 += 1;
+        }
+    }
+
+    let mut ws = Workspace::default();
+    ws.register_as::<IntBuffer, dyn MyBuffer>(|b| b);
+    ws.register_as::<FloatBuffer, dyn MyBuffer>(|b| b);
+    ws.get_or_default::<IntBuffer>().0.push(1);
+    ws.get_or_default::<FloatBuffer>().0.push(2.0);
+    let _ = ws.get_or_default::<A>();
+
+    let mut names = RecordNames(Vec::new());
+    ws.for_each_registered(&mut names);
+    names.0.sort_unstable();
+    assert_eq!(names.0, vec!["float", "int"]);
+
+    let mut reset = Reset(0);
+    ws.for_each_registered(&mut reset);
+    assert_eq!(reset.0, 2);
+    assert!(ws.try_get::<IntBuffer>().unwrap().0.is_empty());
+    assert!(ws.try_get::<FloatBuffer>().unwrap().0.is_empty());
+}
+
+define_thread_local_reentrant_workspace!(REENTRANT_WORKSPACE);
+
+#[test]
+fn reentrant_workspace_allows_nested_access_to_distinct_types() {
+    let sum = with_thread_local_reentrant_workspace(&REENTRANT_WORKSPACE, |a: &mut Vec<u8>| {
+        a.push(1);
+        with_thread_local_reentrant_workspace(&REENTRANT_WORKSPACE, |b: &mut Vec<u16>| {
+            b.push(2);
+            with_thread_local_reentrant_workspace(&REENTRANT_WORKSPACE, |c: &mut A| {
+                c.0 += 3;
+                a[0] as usize + b[0] as usize + c.0
+            })
+        })
+    });
+    assert_eq!(sum, 6);
+    REENTRANT_WORKSPACE.with(|ws| assert_eq!(ws.len(), 3));
+}
+
+#[test]
+#[should_panic(expected = "Can not recursively access the object of type")]
+fn reentrant_workspace_panics_on_nested_access_to_same_type() {
+    let ws = ReentrantWorkspace::new();
+    ws.with(|_: &mut Vec<u8>| {
+        ws.with(|_: &mut u32| {
+            ws.with(|_: &mut Vec<u8>| {});
+        });
+    });
+}
+
+#[test]
+fn workspace_clone_registered_into_copies_registered_types() {
+    let mut src = Workspace::default();
+    src.register_clone::<Vec<u32>>();
+    src.register_clone::<String>();
+    src.get_or_default::<Vec<u32>>().extend([1, 2, 3]);
+    src.get_or_default::<String>().push_str("warm");
+    // Not registered, and therefore not cloned
+    src.get_or_default::<Vec<u8>>().push(1);
+
+    let mut dst = Workspace::default();
+    dst.get_or_default::<String>().push_str("replaced");
+    src.clone_registered_into(&mut dst);
+
+    assert_eq!(dst.try_get::<Vec<u32>>().unwrap(), &[1, 2, 3]);
+    assert_eq!(dst.try_get::<String>().unwrap(), "warm");
+    assert!(dst.try_get::<Vec<u8>>().is_none());
+
+    dst.get_or_default::<Vec<u32>>().push(4);
+    dst.get_or_default::<String>().clear();
+    assert_eq!(src.try_get::<Vec<u32>>().unwrap(), &[1, 2, 3]);
+    assert_eq!(src.try_get::<String>().unwrap(), "warm");
+
+    // Registrations carry over to the destination
+    let mut third = Workspace::default();
+    dst.clone_registered_into(&mut third);
+    assert_eq!(third.try_get::<Vec<u32>>().unwrap(), &[1, 2, 3, 4]);
+}
+
+#[test]
+fn workspace_get_or_build_constructs_from_context_once() {
+    struct Allocator {
+        builds: Cell<usize>,
+        id: u32,
+    }
+
+    struct Arena {
+        allocator_id: u32,
+        used: usize,
+    }
+
+    impl BuildFromContext<Allocator> for Arena {
+        fn build(allocator: &Allocator) -> Self {
+            allocator.builds.set(allocator.builds.get() + 1);
+            Arena {
+                allocator_id: allocator.id,
+                used: 0,
+            }
+        }
+    }
+
+    let allocator = Allocator {
+        builds: Cell::new(0),
+        id: 7,
+    };
+    let mut ws = Workspace::default();
+    ws.get_or_build::<Arena, _>(&allocator).used += 10;
+    let arena: &mut Arena = ws.get_or_build(&allocator);
+    assert_eq!(arena.allocator_id, 7);
+    assert_eq!(arena.used, 10);
+    assert_eq!(allocator.builds.get(), 1);
+}
+
+#[test]
+fn workspace_get_pair_mut_returns_distinct_objects() {
+    let mut ws = Workspace::default();
+    ws.get_or_default::<Vec<u32>>().push(1);
+    let _ = ws.get_or_default::<A>();
+
+    let (a, b) = ws.get_pair_mut::<Vec<u32>, String>();
+    a.push(2);
+    b.push_str("ab");
+    let (b, a) = ws.get_pair_mut::<String, Vec<u32>>();
+    assert_eq!(b, "ab");
+    assert_eq!(a, &[1, 2]);
+}
+
+#[test]
+fn workspace_get_pair_mut_retains_both_objects_under_memory_budget() {
+    let mut ws = Workspace::default();
+    ws.register_memory_usage::<Vec<u8>>();
+    ws.register_memory_usage::<Vec<u16>>();
+    ws.register_memory_usage::<Vec<u32>>();
+    ws.get_or_default::<Vec<u32>>().reserve_exact(25);
+    ws.get_or_default::<Vec<u8>>().extend_from_slice(&[1; 100]);
+    ws.get_or_default::<Vec<u16>>().extend_from_slice(&[2; 50]);
+    ws.set_memory_budget(Some(150));
+
+    let (a, b) = ws.get_pair_mut::<Vec<u8>, Vec<u16>>();
+    assert_eq!(a.len(), 100);
+    assert_eq!(b.len(), 50);
+    // Objects other than the pair are still evicted to enforce the budget
+    assert!(ws.try_get::<Vec<u32>>().is_none());
+    let (b, a) = ws.get_pair_mut::<Vec<u16>, Vec<u8>>();
+    assert_eq!(b.len(), 50);
+    assert_eq!(a.len(), 100);
+}
+
+#[test]
+#[should_panic(expected = "same type twice")]
+fn workspace_get_pair_mut_panics_for_same_type() {
+    let mut ws = Workspace::default();
+    let _ = ws.get_pair_mut::<Vec<u32>, Vec<u32>>();
+}
+
+define_thread_local_workspace!(PAIR_WORKSPACE);
+
+#[test]
+fn with_thread_local_workspace_pair_passes_two_buffers() {
+    fn populate_and_sum(n: u32) -> u32 {
+        with_thread_local_workspace_pair(&PAIR_WORKSPACE, |a: &mut Vec<u32>, b: &mut Vec<u64>| {
+            a.clear();
+            b.clear();
+            a.extend(0..n);
+            b.extend((0..n).map(u64::from));
+            a.iter().sum::<u32>() + b.iter().sum::<u64>() as u32
+        })
+    }
+
+    assert_eq!(populate_and_sum(4), 12);
+    assert_eq!(populate_and_sum(3), 6);
+    PAIR_WORKSPACE.with(|ws| assert!(ws.borrow().try_get::<Vec<u64>>().is_some()));
+}
+
+#[test]
+fn with_thread_local_workspace_pair_applies_memory_policies() {
+    define_thread_local_workspace!(WORKSPACE);
+    let policy = MemoryPolicy::RetainAtMost { max_capacity: 8 };
+    with_thread_local_workspace_with_policy(&WORKSPACE, policy, |_: &mut Vec<u32>| ());
+    with_thread_local_workspace_with_policy(&WORKSPACE, policy, |_: &mut String| ());
+
+    with_thread_local_workspace_pair(&WORKSPACE, |a: &mut Vec<u32>, b: &mut String| {
+        a.extend(0..100);
+        b.push_str(&"x".repeat(100));
+        a.clear();
+        b.clear();
+    });
+    WORKSPACE.with(|ws| {
+        let ws = ws.borrow();
+        assert!(ws.try_get::<Vec<u32>>().unwrap().capacity() <= 8);
+        assert!(ws.try_get::<String>().unwrap().capacity() <= 8);
+    });
+}
+
+#[test]
+fn workspace_get_pair_mut_keeps_memory_budget_if_default_panics() {
+    struct PanicsOnDefault;
+
+    impl Default for PanicsOnDefault {
+        fn default() -> Self {
+            panic!("no default")
+        }
+    }
+
+    let mut ws = Workspace::default();
+    ws.register_memory_usage::<Vec<u8>>();
+    ws.register_memory_usage::<Vec<u16>>();
+    ws.set_memory_budget(Some(100));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _ = ws.get_pair_mut::<Vec<u8>, PanicsOnDefault>();
+    }));
+    assert!(result.is_err());
+
+    ws.get_or_default::<Vec<u8>>().reserve_exact(100);
+    ws.get_or_default::<Vec<u16>>().reserve_exact(50);
+    let _ = ws.get_or_default::<Vec<u16>>();
+    assert!(ws.try_get::<Vec<u8>>().is_none());
+}
+
+#[test]
+fn workspace_entries_idle_for_reports_stale_entries() {
+    let mut ws = Workspace::default();
+    ws.get_or_insert_with(|| B(0));
+    let _ = ws.get_or_default::<A>();
+    assert_eq!(ws.entries_idle_for(1), vec![TypeId::of::<B>()]);
+
+    for _ in 0..9 {
+        let _ = ws.get_or_default::<A>();
+    }
+    assert_eq!(ws.entries_idle_for(10), vec![TypeId::of::<B>()]);
+    assert!(ws.entries_idle_for(11).is_empty());
+    assert_eq!(ws.entries_idle_for(0).len(), 2);
+
+    ws.get_or_insert_with(|| B(0));
+    assert_eq!(ws.entries_idle_for(1), vec![TypeId::of::<A>()]);
+
+    // Inserted objects start out fresh
+    assert!(ws.try_insert(1u8).is_some());
+    assert_eq!(ws.entries_idle_for(1), vec![TypeId::of::<A>()]);
+}
+
+define_thread_local_workspace!(CAPPED_WORKSPACE);
+
+#[test]
+fn capped_slice_limits_retained_capacity() {
+    let sum = with_thread_local_capped_slice(&CAPPED_WORKSPACE, 10_000, 64, |s: &mut [u32]| {
+        s.fill(1);
+        s.iter().sum::<u32>()
+    });
+    assert_eq!(sum, 10_000);
+    CAPPED_WORKSPACE.with(|ws| {
+        let ws = ws.borrow();
+        let buffer = ws.try_get::<Vec<u32>>().unwrap();
+        assert!(buffer.capacity() <= 64);
+        assert!(buffer.len() <= 64);
+    });
+
+    // Small uses stay within the cap and keep their allocation
+    with_thread_local_capped_slice(&CAPPED_WORKSPACE, 32, 64, |s: &mut [u32]| s.fill(2));
+    let len = with_thread_local_capped_slice(&CAPPED_WORKSPACE, 32, 64, |s: &mut [u32]| {
+        assert!(s.iter().all(|&x| x == 2));
+        s.len()
+    });
+    assert_eq!(len, 32);
+}
+
+#[test]
+fn memory_policy_retain_at_most_truncates_strings_on_char_boundary() {
+    let policy = MemoryPolicy::RetainAtMost { max_capacity: 4 };
+    let mut ws = Workspace::default();
+    ws.get_or_default_with_policy::<String>(policy)
+        .push_str("abcæøå");
+    ws.apply_memory_policy::<String>();
+    assert_eq!(ws.try_get::<String>().unwrap(), "abc");
+
+    let mut ws = Workspace::default();
+    ws.get_capped_slice_or_default::<u8>(100, 8);
+    ws.apply_memory_policy::<Vec<u8>>();
+    assert_eq!(ws.try_get::<Vec<u8>>().unwrap().len(), 8);
+}
+
+#[test]
+fn workspace_take_or_default_removes_object() {
+    let mut ws = Workspace::default();
+    ws.get_or_default::<Vec<u32>>().extend([1, 2]);
+    let _ = ws.get_or_default::<A>();
+
+    let taken: Vec<u32> = ws.take_or_default();
+    assert_eq!(taken, vec![1, 2]);
+    assert!(ws.try_get::<Vec<u32>>().is_none());
+    assert!(ws.take_or_default::<Vec<u32>>().is_empty());
+    assert!(ws.try_get::<A>().is_some());
+}
+
+#[test]
+fn workspace_get_dyn_mut_fetches_by_runtime_type_id() {
+    let mut ws = Workspace::default();
+    ws.get_or_default::<A>().0 = 5;
+    ws.get_or_insert_with(|| B(6));
+
+    let ids = [TypeId::of::<A>(), TypeId::of::<String>()];
+    let a = ws.get_dyn_mut(ids[0]).unwrap();
+    let a: &mut A = a.downcast_mut().unwrap();
+    a.0 += 1;
+    assert!(ws.get_dyn_mut(ids[1]).is_none());
+    assert_eq!(ws.try_get::<A>().unwrap().0, 6);
+}
+
+#[test]
+fn workspace_cells_can_be_borrowed_simultaneously() {
+    let mut ws = Workspace::default();
+    ws.get_cell::<A>().borrow_mut().0 = 1;
+    ws.get_cell::<Vec<u32>>().borrow_mut().push(2);
+    assert!(ws.try_get::<A>().is_none());
+
+    let a = ws.try_get_cell::<A>().unwrap();
+    let v = ws.try_get_cell::<Vec<u32>>().unwrap();
+    let mut a_guard = a.borrow_mut();
+    let mut v_guard = v.borrow_mut();
+    a_guard.0 += v_guard[0] as usize;
+    v_guard.push(3);
+    assert!(a.try_borrow().is_err());
+    drop((a_guard, v_guard));
+
+    assert_eq!(ws.get_cell::<A>().borrow().0, 3);
+    assert_eq!(*ws.get_cell::<Vec<u32>>().borrow(), vec![2, 3]);
+}
+
+#[test]
+fn scoped_workspace_caches_objects_borrowing_locals() {
+    struct Scratch<'a> {
+        input: &'a [u32],
+        buffer: Vec<u32>,
+    }
+
+    struct ScratchKey;
+
+    let input = vec![3, 1, 2];
+    let creations = Cell::new(0);
+    let (sorted, len) = scope_with_borrowed_workspace(|ws| {
+        let mut sorted = Vec::new();
+        for _ in 0..3 {
+            // SAFETY: ScratchKey is only used with Scratch<'_> borrowing `input`
+            let scratch = unsafe {
+                ws.get_or_insert_with::<ScratchKey, Scratch<'_>>(|| {
+                    creations.set(creations.get() + 1);
+                    Scratch {
+                        input: &input,
+                        buffer: Vec::new(),
+                    }
+                })
+            };
+            scratch.buffer.clear();
+            scratch.buffer.extend_from_slice(scratch.input);
+            scratch.buffer.sort_unstable();
+            sorted = scratch.buffer.clone();
+        }
+        (sorted, ws.len())
+    });
+
+    assert_eq!(sorted, vec![1, 2, 3]);
+    assert_eq!(len, 1);
+    assert_eq!(creations.get(), 1);
+}
+
+#[test]
+fn scoped_workspace_drops_objects_with_scope() {
+    struct DropFlag<'a>(&'a Cell<bool>);
+
+    impl Drop for DropFlag<'_> {
+        fn drop(&mut self) {
+            self.0.set(true);
+        }
+    }
+
+    struct Key;
+
+    let dropped = Cell::new(false);
+    scope_with_borrowed_workspace(|ws| {
+        // SAFETY: Key is only used with DropFlag<'_>
+        unsafe { ws.get_or_insert_with::<Key, DropFlag<'_>>(|| DropFlag(&dropped)) };
+        assert!(!dropped.get());
+    });
+    assert!(dropped.get());
+}
+
+define_thread_local_workspace!(RAW_WORKSPACE);
+
+#[test]
+fn with_thread_local_workspace_raw_borrows_whole_workspace() {
+    let total = with_thread_local_workspace_raw(&RAW_WORKSPACE, |ws| {
+        ws.get_or_default::<Vec<u32>>().push(1);
+        ws.get_or_default::<A>().0 = 2;
+        let handle = ws.handle::<Vec<u32>>();
+        handle.get(ws).unwrap().push(3);
+        ws.try_get::<Vec<u32>>().unwrap().iter().sum::<u32>() as usize
+            + ws.try_get::<A>().unwrap().0
+    });
+    assert_eq!(total, 6);
+    let len = with_thread_local_workspace(&RAW_WORKSPACE, |buffer: &mut Vec<u32>| buffer.len());
+    assert_eq!(len, 2);
+}
+
+#[test]
+fn workspace_lookups_are_independent_of_order() {
+    let mut ws = Workspace::default();
+    ws.get_or_default::<A>().0 = 1;
+    ws.get_or_insert_with(|| B(2));
+    ws.get_or_default::<Vec<u8>>().push(3);
+    ws.get_or_default::<String>().push('4');
+
+    let ids = [
+        TypeId::of::<A>(),
+        TypeId::of::<B>(),
+        TypeId::of::<Vec<u8>>(),
+        TypeId::of::<String>(),
+    ];
+    let orders: [&[TypeId]; 4] = [
+        &[ids[3], ids[2], ids[1], ids[0]],
+        &[ids[1], ids[3]],
+        &[TypeId::of::<u64>(), ids[2]],
+        &[],
+    ];
+    for order in orders {
+        ws.set_order_for_testing(order);
+        assert_eq!(ws.try_get::<A>().unwrap().0, 1);
+        assert_eq!(ws.try_get::<B>().unwrap().0, 2);
+        assert_eq!(ws.try_get::<Vec<u8>>().unwrap(), &[3]);
+        assert_eq!(ws.get_or_default::<String>(), "4");
+        assert_eq!(ws.get_or_insert_with(|| B(0)).0, 2);
+    }
+}
+
+define_thread_local_workspace!(TRACKED_WORKSPACE);
+
+#[test]
+fn with_thread_local_workspace_tracked_reports_mutation() {
+    let (len, dirty) =
+        with_thread_local_workspace_tracked(&TRACKED_WORKSPACE, |v: &mut DirtyTracked<Vec<u8>>| {
+            v.get_mut().push(1);
+            v.len()
+        });
+    assert_eq!(len, 1);
+    assert!(dirty);
+
+    let (len, dirty) =
+        with_thread_local_workspace_tracked(&TRACKED_WORKSPACE, |v: &mut DirtyTracked<Vec<u8>>| {
+            v.get().len() + v.iter().count()
+        });
+    assert_eq!(len, 2);
+    assert!(!dirty);
+}
+
+define_thread_local_workspace!(FRESH_WORKSPACE);
+
+#[test]
+fn with_fresh_thread_local_workspace_isolates_state() {
+    fn leftover_len() -> usize {
+        with_thread_local_workspace(&FRESH_WORKSPACE, |buffer: &mut Vec<u8>| {
+            let len = buffer.len();
+            buffer.push(0);
+            len
+        })
+    }
+
+    // Without a reset, state leaks between uses
+    assert_eq!(leftover_len(), 0);
+    assert_eq!(leftover_len(), 1);
+
+    assert_eq!(
+        with_fresh_thread_local_workspace(&FRESH_WORKSPACE, leftover_len),
+        0
+    );
+    assert_eq!(leftover_len(), 0);
+}
+
+#[test]
+fn workspace_insert_dyn_stores_opaque_objects() {
+    fn host_scratch() -> (TypeId, Box<dyn std::any::Any>) {
+        let scratch: Box<dyn std::any::Any> = Box::new(vec![1u16, 2]);
+        ((*scratch).type_id(), scratch)
+    }
+
+    let mut ws = Workspace::default();
+    let (id, scratch) = host_scratch();
+    assert!(ws.insert_dyn(id, scratch).is_none());
+
+    let scratch = ws.get_dyn_mut(id).unwrap();
+    scratch.downcast_mut::<Vec<u16>>().unwrap().push(3);
+    assert_eq!(ws.get_or_default::<Vec<u16>>(), &[1, 2, 3]);
+
+    let (id, scratch) = host_scratch();
+    let previous = ws.insert_dyn(id, scratch).unwrap();
+    assert_eq!(*previous.downcast::<Vec<u16>>().unwrap(), vec![1, 2, 3]);
+    assert_eq!(ws.try_get::<Vec<u16>>().unwrap(), &[1, 2]);
+}
+
+#[test]
+fn workspace_insert_dyn_keeps_policy_and_resetter_of_replaced_object() {
+    let mut ws = Workspace::default();
+    let policy = MemoryPolicy::RetainAtMost { max_capacity: 4 };
+    ws.get_or_default_with_policy::<Vec<u16>>(policy);
+    ws.get_or_insert_with_resetter(Vec::<u16>::new, Vec::clear);
+
+    let replacement: Vec<u16> = (0..100).collect();
+    assert!(ws
+        .insert_dyn(TypeId::of::<Vec<u16>>(), Box::new(replacement))
+        .is_some());
+    assert!(ws.reset_type::<Vec<u16>>());
+    ws.apply_memory_policy::<Vec<u16>>();
+    let buffer = ws.try_get::<Vec<u16>>().unwrap();
+    assert!(buffer.is_empty());
+    assert!(buffer.capacity() <= 4);
+}
+
+#[test]
+#[should_panic(expected = "does not match the type")]
+fn workspace_insert_dyn_panics_on_mismatched_type_id() {
+    let mut ws = Workspace::default();
+    ws.insert_dyn(TypeId::of::<u8>(), Box::new(1u16));
+}
+
+#[test]
+fn workspace_get_or_default_reserving_sizes_composite_types() {
+    #[derive(Default)]
+    struct Buffers {
+        indices: Vec<usize>,
+        queue: std::collections::VecDeque<u8>,
+        label: String,
+    }
+
+    impl WorkspaceReserve for Buffers {
+        fn reserve_for(&mut self, hint: usize) {
+            self.indices.reserve_for(hint);
+            self.queue.reserve_for(2 * hint);
+            self.label.reserve_for(hint / 2);
+        }
+    }
+
+    let mut ws = Workspace::default();
+    let buffers: &mut Buffers = ws.get_or_default_reserving(50);
+    assert!(buffers.indices.capacity() >= 50);
+    assert!(buffers.queue.capacity() >= 100);
+    assert!(buffers.label.capacity() >= 25);
+
+    buffers.indices.extend(0..10);
+    let buffers: &mut Buffers = ws.get_or_default_reserving(200);
+    assert!(buffers.indices.capacity() >= 200);
+    assert_eq!(buffers.indices.len(), 10);
+}
+
+#[test]
+fn workspace_peek_lru_and_mru_follow_internal_order() {
+    #[derive(Default)]
+    struct C;
+
+    let mut ws = Workspace::default();
+    assert_eq!(ws.peek_lru_type_id(), None);
+    assert_eq!(ws.peek_mru_type_id(), None);
+
+    let _ = ws.get_or_default::<A>();
+    ws.get_or_insert_with(|| B(0));
+    let _ = ws.get_or_default::<C>();
+    assert_eq!(ws.peek_lru_type_id(), Some(TypeId::of::<A>()));
+    assert_eq!(ws.peek_mru_type_id(), Some(TypeId::of::<C>()));
+
+    // Accessing A moves it to the back, so B becomes the least recently used object
+    let _ = ws.get_or_default::<A>();
+    assert_eq!(ws.peek_mru_type_id(), Some(TypeId::of::<A>()));
+    assert_eq!(ws.peek_lru_type_id(), Some(TypeId::of::<B>()));
+
+    // Lookups that do not count as use leave the order untouched
+    let _ = ws.try_get::<B>();
+    assert_eq!(ws.peek_mru_type_id(), Some(TypeId::of::<A>()));
+}
+
+define_thread_local_workspace!(ENUM_WORKSPACE);
+
+#[test]
+fn enum_workspace_keeps_one_buffer_per_variant() {
+    #[derive(Clone, Copy)]
+    enum BufferKind {
+        Small,
+        Large,
+        Sized(usize),
+    }
+
+    fn fill(kind: BufferKind) -> usize {
+        with_thread_local_workspace(
+            &ENUM_WORKSPACE,
+            |buffers: &mut EnumWorkspace<BufferKind, Vec<u8>>| {
+                let buffer = buffers.get_or_default(&kind);
+                let n = match kind {
+                    BufferKind::Small => 1,
+                    BufferKind::Large => 100,
+                    BufferKind::Sized(n) => n,
+                };
+                buffer.extend(std::iter::repeat_n(0, n));
+                buffer.len()
+            },
+        )
+    }
+
+    let kinds = [BufferKind::Small, BufferKind::Large, BufferKind::Sized(5)];
+    let first: Vec<_> = kinds.iter().map(|&kind| fill(kind)).collect();
+    let second: Vec<_> = kinds.iter().map(|&kind| fill(kind)).collect();
+    assert_eq!(first, vec![1, 100, 5]);
+    assert_eq!(second, vec![2, 200, 10]);
+
+    // The data carried by a variant does not select a different buffer
+    assert_eq!(fill(BufferKind::Sized(1)), 11);
+    with_thread_local_workspace(
+        &ENUM_WORKSPACE,
+        |buffers: &mut EnumWorkspace<BufferKind, Vec<u8>>| {
+            assert_eq!(buffers.len(), 3);
+            assert_eq!(buffers.try_get(&BufferKind::Small).unwrap().len(), 2);
+        },
+    );
+}
+
+#[test]
+fn workspace_alloc_observer_reports_created_and_dropped() {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let mut ws = Workspace::default();
+    ws.register_memory_usage::<Vec<u64>>();
+    let recorded = Rc::clone(&events);
+    ws.set_alloc_observer(Box::new(move |event| recorded.borrow_mut().push(event)));
+
+    let vec_id = TypeId::of::<Vec<u64>>();
+    let a_id = TypeId::of::<A>();
+    ws.get_or_default::<Vec<u64>>().reserve_exact(4);
+    let _ = ws.get_or_default::<A>();
+    assert!(ws.invalidate::<Vec<u64>>());
+    assert!(ws.try_insert(Vec::<u64>::with_capacity(2)).is_some());
+    drop(ws);
+
+    let events = events.borrow();
+    assert_eq!(events.len(), 6);
+    assert_eq!(
+        events[..2],
+        [
+            AllocEvent::Created {
+                type_id: vec_id,
+                bytes: 0
+            },
+            AllocEvent::Created {
+                type_id: a_id,
+                bytes: 0
+            },
+        ]
+    );
+    assert!(matches!(
+        events[2],
+        AllocEvent::Dropped { type_id, bytes } if type_id == vec_id && bytes >= 32
+    ));
+    assert!(matches!(
+        events[3],
+        AllocEvent::Created { type_id, bytes } if type_id == vec_id && bytes >= 16
+    ));
+    let mut dropped: Vec<_> = events[4..]
+        .iter()
+        .map(|event| match *event {
+            AllocEvent::Dropped { type_id, .. } => type_id,
+            AllocEvent::Created { .. } => panic!("Expected only drops"),
+        })
+        .collect();
+    dropped.sort_unstable();
+    let mut expected = vec![vec_id, a_id];
+    expected.sort_unstable();
+    assert_eq!(dropped, expected);
+}
+
+#[test]
+fn workspace_alloc_observer_reports_objects_moved_out_and_replaced() {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let mut ws = Workspace::default();
+    ws.register_memory_usage::<Vec<u64>>();
+    let recorded = Rc::clone(&events);
+    ws.set_alloc_observer(Box::new(move |event| recorded.borrow_mut().push(event)));
+
+    let vec_id = TypeId::of::<Vec<u64>>();
+    let a_id = TypeId::of::<A>();
+    let dropped = |type_id, bytes| AllocEvent::Dropped { type_id, bytes };
+    let created = |type_id, bytes| AllocEvent::Created { type_id, bytes };
+
+    ws.get_or_default::<Vec<u64>>().reserve_exact(4);
+    let taken = ws.take_or_default::<Vec<u64>>();
+    assert_eq!(*events.borrow(), [created(vec_id, 0), dropped(vec_id, 32)]);
+    events.borrow_mut().clear();
+
+    assert!(ws.try_insert(taken).is_some());
+    let _ = ws.get_or_default::<A>();
+    let extracted = ws.extract_if(|id| id == vec_id);
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(
+        *events.borrow(),
+        [created(vec_id, 32), created(a_id, 0), dropped(vec_id, 32)]
+    );
+    events.borrow_mut().clear();
+
+    let previous = ws.insert_dyn(a_id, Box::new(A::default()));
+    assert!(previous.is_some());
+    let replacement = Vec::<u64>::with_capacity(2);
+    let bytes = 8 * replacement.capacity();
+    assert!(ws.insert_dyn(vec_id, Box::new(replacement)).is_none());
+    let previous = ws.insert_dyn(vec_id, Box::new(Vec::<u64>::new())).unwrap();
+    assert!(previous.downcast::<Vec<u64>>().is_ok());
+    assert_eq!(
+        *events.borrow(),
+        [
+            dropped(a_id, 0),
+            created(a_id, 0),
+            created(vec_id, bytes),
+            dropped(vec_id, bytes),
+            created(vec_id, 0)
+        ]
+    );
+}
+
+define_thread_local_workspace!(CONTROLLED_WORKSPACE);
+
+#[test]
+fn with_thread_local_workspace_controlled_applies_after_use() {
+    fn push(after_use: AfterUse) -> (usize, usize) {
+        with_thread_local_workspace_controlled(&CONTROLLED_WORKSPACE, |buffer: &mut Vec<u8>| {
+            buffer.push(1);
+            ((buffer.len(), buffer.capacity()), after_use)
+        })
+    }
+
+    let stored = || {
+        CONTROLLED_WORKSPACE.with(|ws| {
+            ws.borrow()
+                .try_get::<Vec<u8>>()
+                .map(|buffer| (buffer.len(), buffer.capacity()))
+        })
+    };
+
+    assert_eq!(push(AfterUse::Keep).0, 1);
+    assert_eq!(push(AfterUse::Keep).0, 2);
+    assert_eq!(stored().map(|(len, _)| len), Some(2));
+
+    assert_eq!(push(AfterUse::Reset).0, 3);
+    assert_eq!(stored(), Some((0, 0)));
+
+    assert_eq!(push(AfterUse::Drop).0, 1);
+    assert_eq!(stored(), None);
+    assert_eq!(push(AfterUse::Keep).0, 1);
+}
+
+#[test]
+fn recycling_vec_of_vecs_keeps_inner_capacities() {
+    let mut scratch = RecyclingVecOfVecs::<usize>::new();
+    let vecs = scratch.prepare(3);
+    assert_eq!(vecs.len(), 3);
+    for (i, inner) in vecs.iter_mut().enumerate() {
+        inner.extend(0..10 * (i + 1));
+    }
+    let capacities: Vec<_> = scratch.get().iter().map(Vec::capacity).collect();
+
+    // Shrinking the outer length keeps the remaining inner vectors for later
+    let vecs = scratch.prepare(1);
+    assert_eq!(vecs.len(), 1);
+    assert!(vecs[0].is_empty());
+    assert_eq!(vecs[0].capacity(), capacities[0]);
+    assert_eq!(scratch.num_allocated(), 3);
+
+    let vecs = scratch.prepare(4);
+    assert_eq!(vecs.len(), 4);
+    assert!(vecs.iter().all(Vec::is_empty));
+    let new_capacities: Vec<_> = vecs.iter().map(Vec::capacity).collect();
+    assert_eq!(new_capacities[..3], capacities[..]);
+    assert_eq!(new_capacities[3], 0);
+}
+
+define_thread_local_workspace!(ASYNC_WORKSPACE);
+
+#[test]
+fn with_thread_local_workspace_async_safe_spills_when_borrowed() {
+    fn task_step(value: u32) -> usize {
+        with_thread_local_workspace_async_safe(&ASYNC_WORKSPACE, |buffer: &mut Vec<u32>| {
+            buffer.push(value);
+            buffer.len()
+        })
+    }
+
+    assert_eq!(task_step(1), 1);
+
+    // Simulate a task that holds the workspace across a yield point, while another task
+    // on the same thread runs
+    let held = ASYNC_WORKSPACE.with(|ws| {
+        let guard = ws.borrow_mut();
+        let other = task_step(2);
+        drop(guard);
+        other
+    });
+    assert_eq!(held, 1);
+
+    // Once released, the thread-local workspace is used again, without the spilled value
+    assert_eq!(task_step(3), 2);
+
+    // Recursive use also spills instead of panicking
+    let nested =
+        with_thread_local_workspace_async_safe(&ASYNC_WORKSPACE, |outer: &mut Vec<u32>| {
+            outer.len() + task_step(4)
+        });
+    assert_eq!(nested, 3);
+}
+
+#[test]
+fn shared_capacity_pool_reuses_one_allocation_across_phases() {
+    struct Assembly;
+    struct Solve;
+
+    let mut ws = Workspace::default();
+    let pool: &SharedCapacityPool<f64> = ws.get_or_default();
+    let ptr = {
+        let mut buffer = pool.checkout::<Assembly>();
+        buffer.resize(1000, 1.0);
+        buffer.as_ptr()
+    };
+    let capacity = pool.capacity();
+    assert!(capacity >= 1000);
+
+    for _ in 0..3 {
+        let mut buffer = pool.checkout::<Solve>();
+        assert!(buffer.is_empty());
+        buffer.resize(500, 2.0);
+        assert_eq!(buffer.as_ptr(), ptr);
+        drop(buffer);
+
+        let mut buffer = pool.checkout::<Assembly>();
+        buffer.resize(1000, 1.0);
+        assert_eq!(buffer.as_ptr(), ptr);
+    }
+    assert_eq!(pool.capacity(), capacity);
+    assert_eq!(ws.iter_of_type::<Vec<f64>>().count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "already checked out by phase")]
+fn shared_capacity_pool_panics_on_simultaneous_checkout() {
+    struct Assembly;
+    struct Solve;
+
+    let pool = SharedCapacityPool::<f64>::new();
+    let _assembly = pool.checkout::<Assembly>();
+    let _solve = pool.checkout::<Solve>();
+}
+
+#[test]
+fn frozen_workspace_allows_existing_types_only() {
+    let mut ws = Workspace::default();
+    ws.get_or_default::<Vec<f64>>().push(1.0);
+    ws.freeze();
+    assert!(ws.is_frozen());
+
+    ws.get_or_default::<Vec<f64>>().push(2.0);
+    assert_eq!(ws.try_get::<Vec<f64>>().unwrap(), &[1.0, 2.0]);
+    let err = ws.try_get_or_default::<Vec<u32>>().unwrap_err();
+    assert!(err.type_name().contains("u32"));
+    assert!(ws.try_get::<Vec<u32>>().is_none());
+
+    ws.unfreeze();
+    assert!(ws.try_get_or_default::<Vec<u32>>().is_ok());
+}
+
+#[test]
+#[should_panic(expected = "into a frozen workspace")]
+fn frozen_workspace_panics_on_new_type() {
+    let mut ws = Workspace::default();
+    ws.get_or_default::<Vec<f64>>();
+    ws.freeze();
+    ws.get_or_default::<Vec<u32>>();
+}
+
+#[test]
+fn poison_overwrites_vec_elements_without_changing_length() {
+    let mut buffer = vec![1u64, 2, 3];
+    buffer.poison();
+    assert_eq!(buffer, [0xDE; 3]);
+}
+
+#[test]
+fn with_thread_local_workspace_take_stores_returned_object() {
+    define_thread_local_workspace!(WORKSPACE);
+
+    let old = with_thread_local_workspace_take(&WORKSPACE, |mut buffer: Vec<u32>| {
+        assert!(buffer.is_empty());
+        buffer.push(1);
+        (vec![7, 8], buffer)
+    });
+    assert_eq!(old, [1]);
+
+    let cached =
+        with_thread_local_workspace_take(&WORKSPACE, |buffer: Vec<u32>| (buffer.clone(), buffer));
+    assert_eq!(cached, [7, 8]);
+}
+
+#[test]
+fn get_array_or_default_persists_state_across_calls() {
+    let mut ws = Workspace::default();
+    for i in 0..3u64 {
+        let array = ws.get_array_or_default::<u64, 8>();
+        array[i as usize] = i + 1;
+    }
+    assert_eq!(
+        ws.get_array_or_default::<u64, 8>(),
+        &[1, 2, 3, 0, 0, 0, 0, 0]
+    );
+    // Arrays of different lengths are distinct types
+    assert_eq!(ws.get_array_or_default::<u64, 4>(), &[0; 4]);
+    assert_eq!(ws.try_get::<[u64; 8]>().unwrap()[2], 3);
+}
+
+struct RecordsDrop<const N: usize>(Rc<RefCell<Vec<usize>>>);
+
+impl<const N: usize> Drop for RecordsDrop<N> {
+    fn drop(&mut self) {
+        self.0.borrow_mut().push(N);
+    }
+}
+
+fn insert_drop_recorders(ws: &mut Workspace, log: &Rc<RefCell<Vec<usize>>>) {
+    let _ = ws.try_insert(RecordsDrop::<0>(Rc::clone(log)));
+    let _ = ws.try_insert(RecordsDrop::<1>(Rc::clone(log)));
+    let _ = ws.try_insert(RecordsDrop::<2>(Rc::clone(log)));
+    // Moves 0 to the back, so that the internal order from front to back is 1, 2, 0
+    ws.touch::<RecordsDrop<0>>();
+}
+
+#[test]
+fn clear_drops_objects_from_most_recently_used() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut ws = Workspace::default();
+    insert_drop_recorders(&mut ws, &log);
+    assert_eq!(ws.peek_mru_type_id(), Some(TypeId::of::<RecordsDrop<0>>()));
+    ws.clear();
+    assert_eq!(*log.borrow(), [0, 2, 1]);
+}
+
+#[test]
+fn dropping_workspace_drops_objects_from_most_recently_used() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut ws = Workspace::default();
+    insert_drop_recorders(&mut ws, &log);
+    drop(ws);
+    assert_eq!(*log.borrow(), [0, 2, 1]);
+}
+
+#[test]
+fn split_off_gives_access_to_other_types() {
+    let mut ws = Workspace::default();
+    ws.get_or_default::<Vec<u32>>().push(1);
+    ws.get_or_default::<String>().push('a');
+
+    let (a, mut rest) = ws.split_off::<Vec<u32>>();
+    let b = rest.get_mut::<String>().unwrap();
+    a.push(2);
+    b.push('b');
+    assert_eq!(rest.len(), 1);
+    assert!(rest.get_mut::<Vec<u32>>().is_none());
+
+    assert_eq!(ws.try_get::<Vec<u32>>().unwrap(), &[1, 2]);
+    assert_eq!(ws.try_get::<String>().unwrap(), "ab");
+    // The split-off type is inserted if not present
+    let (c, rest) = ws.split_off::<Vec<u8>>();
+    assert!(c.is_empty());
+    assert_eq!(rest.len(), 2);
+}
+
+#[test]
+fn with_workspace_field_gives_access_to_field() {
+    #[derive(Default)]
+    struct MyWorkspace {
+        index_buffer: Vec<usize>,
+        other: Vec<usize>,
+    }
+
+    define_thread_local_workspace!(WORKSPACE);
+
+    fn median_floor(indices: &[usize]) -> Option<usize> {
+        with_workspace_field!(WORKSPACE, MyWorkspace, index_buffer, |buffer| {
+            buffer.clear();
+            buffer.extend_from_slice(indices);
+            buffer.sort_unstable();
+            buffer.get(indices.len() / 2).copied()
+        })
+    }
+
+    assert_eq!(median_floor(&[5, 1, 4, 2, 3]), Some(3));
+    assert_eq!(median_floor(&[]), None);
+    with_thread_local_workspace(&WORKSPACE, |workspace: &mut MyWorkspace| {
+        assert!(workspace.index_buffer.capacity() >= 5);
+        assert!(workspace.other.is_empty());
+    });
+}
+
+define_thread_local_workspace!(TYPES_WORKSPACE);
+
+fn use_two_types() {
+    with_thread_local_workspace(&TYPES_WORKSPACE, |buffer: &mut Vec<u32>| buffer.push(1));
+    with_thread_local_workspace(&TYPES_WORKSPACE, |buffer: &mut String| buffer.push('a'));
+}
+
+#[test]
+fn assert_workspace_types_accepts_exact_type_set() {
+    with_fresh_thread_local_workspace(&TYPES_WORKSPACE, || {
+        use_two_types();
+        assert_workspace_types(
+            &TYPES_WORKSPACE,
+            &[TypeId::of::<String>(), TypeId::of::<Vec<u32>>()],
+        );
+    });
+}
+
+#[test]
+#[should_panic(expected = "does not hold exactly the expected types")]
+fn assert_workspace_types_rejects_unexpected_type() {
+    with_fresh_thread_local_workspace(&TYPES_WORKSPACE, || {
+        use_two_types();
+        assert_workspace_types(&TYPES_WORKSPACE, &[TypeId::of::<Vec<u32>>()]);
+    });
+}
+
+#[test]
+fn prewarm_inserts_all_types_with_requested_capacities() {
+    let mut ws = Workspace::default();
+    ws.get_or_default::<Vec<u32>>().push(1);
+    ws.prewarm([
+        PrewarmSpec::reserving::<Vec<f64>>(100),
+        PrewarmSpec::reserving::<Vec<u32>>(50),
+        PrewarmSpec::with(|| Vec::<u8>::with_capacity(200)),
+    ]);
+    assert!(ws.try_get::<Vec<f64>>().unwrap().capacity() >= 100);
+    assert!(ws.try_get::<Vec<u8>>().unwrap().capacity() >= 200);
+    // Existing objects are kept
+    let existing = ws.try_get::<Vec<u32>>().unwrap();
+    assert_eq!(existing, &[1]);
+    assert!(existing.capacity() >= 50);
+}
+
+#[test]
+fn shared_workspace_try_with_distinguishes_busy_from_closure_errors() {
+    #[derive(Debug, PartialEq)]
+    struct Negative;
+
+    fn checked_push(
+        shared: &SharedWorkspace,
+        value: i32,
+    ) -> Result<usize, SharedWorkspaceError<Negative>> {
+        shared.try_with(|buffer: &mut Vec<i32>| {
+            if value < 0 {
+                return Err(Negative);
+            }
+            buffer.push(value);
+            Ok(buffer.len())
+        })
+    }
+
+    let shared = SharedWorkspace::new();
+    assert_eq!(checked_push(&shared, 1), Ok(1));
+    assert_eq!(
+        checked_push(&shared, -1),
+        Err(SharedWorkspaceError::Closure(Negative))
+    );
+
+    let other = shared.clone();
+    let nested = shared.with(|_: &mut Vec<i32>| {
+        assert!(other.is_in_use());
+        checked_push(&other, 2)
+    });
+    assert_eq!(nested, Err(SharedWorkspaceError::Busy(WorkspaceBusy)));
+    assert_eq!(other.with(|buffer: &mut Vec<i32>| buffer.clone()), [1]);
+}
+
+#[test]
+fn trait_slot_swaps_implementations_at_runtime() {
+    trait Strategy {
+        fn apply(&mut self, x: u32) -> u32;
+    }
+
+    struct AddOne;
+    struct Accumulate(u32);
+
+    impl Strategy for AddOne {
+        fn apply(&mut self, x: u32) -> u32 {
+            x + 1
+        }
+    }
+
+    impl Strategy for Accumulate {
+        fn apply(&mut self, x: u32) -> u32 {
+            self.0 += x;
+            self.0
+        }
+    }
+
+    let mut ws = Workspace::default();
+    let slot = ws.get_or_default::<TraitSlot<dyn Strategy>>();
+    assert!(slot.set(Box::new(AddOne)).is_none());
+    assert_eq!(slot.get_mut().unwrap().apply(1), 2);
+
+    let slot = ws.get_or_default::<TraitSlot<dyn Strategy>>();
+    assert!(slot.set(Box::new(Accumulate(10))).is_some());
+    assert_eq!(slot.get_mut().unwrap().apply(1), 11);
+    let slot = ws.get_or_default::<TraitSlot<dyn Strategy>>();
+    assert_eq!(slot.get_mut().unwrap().apply(1), 12);
+    assert_eq!(ws.iter_of_type::<TraitSlot<dyn Strategy>>().count(), 1);
+}
+
+#[test]
+fn try_slice_helpers_report_unreasonable_lengths() {
+    let mut ws = Workspace::default();
+    ws.get_slice_or_default::<u64>(2).copy_from_slice(&[1, 2]);
+    // Exceeds the maximum allocation size, which is detected before attempting to allocate
+    assert!(ws.try_get_slice_or_default::<u64>(usize::MAX / 4).is_err());
+    assert_eq!(ws.try_get::<Vec<u64>>().unwrap(), &[1, 2]);
+    assert_eq!(ws.try_get_slice_or_default::<u64>(3).unwrap(), &[1, 2, 0]);
+
+    ws.get_boxed_slice_or_default::<u64>(2)
+        .copy_from_slice(&[3, 4]);
+    assert!(ws
+        .try_get_boxed_slice_or_default::<u64>(usize::MAX / 4)
+        .is_err());
+    assert_eq!(&**ws.try_get::<Box<[u64]>>().unwrap(), &[3, 4]);
+    assert_eq!(ws.try_get_boxed_slice_or_default::<u64>(1).unwrap(), &[0]);
+}
+
+#[test]
+fn scratch_allocator_reuses_returned_buffers() {
+    let scratch = ScratchAllocator::new();
+    let mut pointers = Vec::new();
+    for i in 0..5 {
+        let mut a = scratch.rent::<Vec<u32>>();
+        let mut b = scratch.rent::<Vec<u32>>();
+        assert!(a.is_empty() && b.is_empty());
+        a.resize(100, i);
+        b.resize(100, i);
+        let mut current = [a.as_ptr(), b.as_ptr()];
+        current.sort_unstable();
+        pointers.push(current);
+    }
+    assert!(pointers.windows(2).all(|w| w[0] == w[1]));
+    assert_eq!(scratch.available::<Vec<u32>>(), 2);
+
+    // Any type implementing `Clear` can be rented, such as maps
+    let capacity = {
+        let mut map = scratch.rent::<HashMap<u32, u32>>();
+        map.extend((0..100).map(|i| (i, i)));
+        map.capacity()
+    };
+    let map = scratch.rent::<HashMap<u32, u32>>();
+    assert!(map.is_empty());
+    assert_eq!(map.capacity(), capacity);
+}
+
+#[test]
+fn bounded_workspace_rejects_type_beyond_max() {
+    let mut ws = BoundedWorkspace::<3>::new();
+    ws.get_or_default::<Vec<u8>>();
+    ws.get_or_default::<Vec<u16>>();
+    assert!(ws.try_get_or_default::<Vec<u32>>().is_ok());
+    assert_eq!(ws.len(), 3);
+
+    let err = ws
+        .try_get_or_insert_with(|| -> Vec<u64> { unreachable!() })
+        .unwrap_err();
+    assert_eq!(err.max(), 3);
+    assert!(err.type_name().contains("u64"));
+    assert_eq!(ws.len(), 3);
+    // Types already present remain accessible
+    ws.get_or_default::<Vec<u8>>().push(1);
+    assert_eq!(ws.try_get::<Vec<u8>>().unwrap(), &[1]);
+}
+
+#[test]
+#[should_panic(expected = "maximum of 1 distinct types")]
+fn bounded_workspace_get_or_default_panics_beyond_max() {
+    let mut ws = BoundedWorkspace::<1>::new();
+    ws.get_or_default::<Vec<u8>>();
+    ws.get_or_default::<Vec<u16>>();
+}
+
+#[test]
+fn reset_type_applies_stored_resetter() {
+    // Deliberately does not implement `Default`, so `get_or_default` can not be used
+    struct Counter {
+        count: u32,
+        resets: u32,
+    }
+
+    let mut ws = Workspace::default();
+    assert!(!ws.reset_type::<Counter>());
+    let counter = ws.get_or_insert_with_resetter(
+        || Counter {
+            count: 0,
+            resets: 0,
+        },
+        |c| {
+            c.count = 0;
+            c.resets += 1;
+        },
+    );
+    counter.count = 5;
+    assert!(ws.reset_type::<Counter>());
+    let counter = ws.try_get::<Counter>().unwrap();
+    assert_eq!((counter.count, counter.resets), (0, 1));
+
+    // Objects inserted without a resetter are left as is
+    let _ = ws.try_insert(7u32);
+    assert!(!ws.reset_type::<u32>());
+    assert_eq!(ws.try_get::<u32>(), Some(&7));
+}
+
+struct Flusher {
+    pending: Vec<u32>,
+    flushed: std::sync::mpsc::Sender<Vec<u32>>,
+}
+
+impl Teardown for Flusher {
+    fn teardown(&mut self, workspace: &mut Workspace) {
+        let scratch = workspace.get_or_default::<Vec<u32>>();
+        scratch.extend(self.pending.drain(..).map(|x| 2 * x));
+        self.flushed.send(scratch.clone()).unwrap();
+    }
+}
+
+#[test]
+fn teardown_accesses_sibling_objects_on_clear() {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut ws = Workspace::default();
+    ws.register_teardown::<Flusher>();
+    ws.get_or_default::<Vec<u32>>().push(1);
+    ws.get_or_insert_with(|| Flusher {
+        pending: vec![2, 3],
+        flushed: sender,
+    });
+    ws.clear();
+    assert_eq!(receiver.try_recv().unwrap(), [1, 4, 6]);
+    assert!(ws.try_get::<Vec<u32>>().is_none());
+}
+
+#[test]
+fn teardown_runs_for_thread_local_workspace_at_thread_exit() {
+    define_thread_local_workspace!(WORKSPACE);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        with_thread_local_workspace_raw(&WORKSPACE, |ws| {
+            ws.register_teardown::<Flusher>();
+            ws.get_or_insert_with(|| Flusher {
+                pending: vec![5],
+                flushed: sender,
+            });
+        });
+    })
+    .join()
+    .unwrap();
+    assert_eq!(receiver.recv().unwrap(), [10]);
+}
+
+#[test]
+fn scoped_set_workspace_value_restores_previous_value() {
+    define_thread_local_workspace!(WORKSPACE);
+
+    fn read() -> Vec<u32> {
+        with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| buffer.clone())
+    }
+
+    with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| buffer.push(1));
+    let inner = scoped_set_workspace_value(&WORKSPACE, vec![7u32, 8], || {
+        let inner = read();
+        // Modifications within the scope are discarded afterwards
+        with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| buffer.clear());
+        inner
+    });
+    assert_eq!(inner, [7, 8]);
+    assert_eq!(read(), [1]);
+
+    // Without a previous value, the value is removed afterwards
+    scoped_set_workspace_value(&WORKSPACE, String::from("scoped"), || {
+        with_thread_local_workspace_raw(&WORKSPACE, |ws| {
+            assert_eq!(ws.try_get::<String>().unwrap(), "scoped");
+        });
+    });
+    with_thread_local_workspace_raw(&WORKSPACE, |ws| assert!(ws.try_get::<String>().is_none()));
+
+    // The previous value is also restored if the closure panics
+    let result = std::panic::catch_unwind(|| {
+        scoped_set_workspace_value(&WORKSPACE, vec![9u32], || panic!("inside scope"))
+    });
+    assert!(result.is_err());
+    assert_eq!(read(), [1]);
+}
+
+#[test]
+fn manually_dropped_values_are_not_dropped_by_clear() {
+    struct CountsDrops(Rc<Cell<usize>>);
+
+    impl Drop for CountsDrops {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    let drops = Rc::new(Cell::new(0));
+    let mut ws = Workspace::default();
+    assert!(ws
+        .insert_manually_dropped(CountsDrops(Rc::clone(&drops)))
+        .is_none());
+    assert!(ws.get_manually_dropped::<CountsDrops>().is_some());
+    assert!(ws.try_get::<CountsDrops>().is_none());
+    ws.clear();
+    assert_eq!(drops.get(), 0);
+
+    let previous = ws.insert_manually_dropped(CountsDrops(Rc::clone(&drops)));
+    assert!(previous.is_none());
+    let taken = ws.take_manually_dropped::<CountsDrops>().unwrap();
+    assert!(ws.take_manually_dropped::<CountsDrops>().is_none());
+    drop(std::mem::ManuallyDrop::into_inner(taken));
+    assert_eq!(drops.get(), 1);
+}
+
+#[test]
+fn shared_workspace_guard_maps_to_field() {
+    #[derive(Default)]
+    struct MyWorkspace {
+        index_buffer: Vec<usize>,
+        values: Vec<f64>,
+    }
+
+    let shared = SharedWorkspace::new();
+    {
+        let mut indices = shared
+            .get_or_default::<MyWorkspace>()
+            .map(|w| &mut w.index_buffer);
+        indices.extend([2, 1]);
+        indices.sort_unstable();
+        assert!(shared.is_in_use());
+        assert!(shared.try_get_or_default::<MyWorkspace>().is_err());
+    }
+    assert!(!shared.is_in_use());
+
+    let workspace = shared.get_or_default::<MyWorkspace>();
+    assert_eq!(workspace.index_buffer, [1, 2]);
+    assert!(workspace.values.is_empty());
+}
+
+#[repr(align(64))]
+#[derive(Default, Clone)]
+struct OverAligned([u8; 32]);
+
+#[test]
+fn over_aligned_types_are_reused_with_alignment() {
+    define_thread_local_workspace!(WORKSPACE);
+
+    fn address_after_increment() -> usize {
+        with_thread_local_workspace(&WORKSPACE, |w: &mut OverAligned| {
+            w.0[0] += 1;
+            w as *const OverAligned as usize
+        })
+    }
+
+    let address = address_after_increment();
+    assert_eq!(address % 64, 0);
+    assert_eq!(address_after_increment(), address);
+
+    with_thread_local_workspace_controlled(&WORKSPACE, |_: &mut OverAligned| ((), AfterUse::Reset));
+    with_thread_local_workspace_take(&WORKSPACE, |w: OverAligned| (w, ()));
+    assert_eq!(address_after_increment(), address);
+    with_thread_local_workspace(&WORKSPACE, |w: &mut OverAligned| assert_eq!(w.0[0], 1));
+
+    let mut ws = Workspace::default();
+    let address = ws.get_or_default::<OverAligned>() as *const OverAligned as usize;
+    assert_eq!(address % 64, 0);
+    ws.get_or_default::<Vec<u8>>();
+    assert_eq!(
+        ws.get_or_default::<OverAligned>() as *const _ as usize,
+        address
+    );
+}
+
+#[test]
+fn clone_registered_into_reuses_existing_objects() {
+    let mut src = Workspace::default();
+    src.register_clone::<OverAligned>();
+    src.register_clone::<Vec<u32>>();
+    src.get_or_default::<OverAligned>().0[0] = 7;
+    src.get_or_default::<Vec<u32>>().extend([1, 2]);
+
+    let mut dst = Workspace::default();
+    src.clone_registered_into(&mut dst);
+    let address = dst.try_get::<OverAligned>().unwrap() as *const OverAligned as usize;
+    assert_eq!(address % 64, 0);
+    let buffer = dst.get_or_default::<Vec<u32>>();
+    buffer.reserve(100);
+    let buffer_ptr = buffer.as_ptr();
+
+    src.get_or_default::<OverAligned>().0[0] = 8;
+    src.clone_registered_into(&mut dst);
+    let cloned = dst.try_get::<OverAligned>().unwrap();
+    assert_eq!(cloned.0[0], 8);
+    assert_eq!(cloned as *const OverAligned as usize, address);
+    assert_eq!(dst.try_get::<Vec<u32>>().unwrap().as_ptr(), buffer_ptr);
+}
+
+#[test]
+fn thread_local_workspace_iter_reuses_buffer_lazily() {
+    define_thread_local_workspace!(WORKSPACE);
+
+    fn doubled(values: &[u32]) -> impl Iterator<Item = u32> + '_ {
+        thread_local_workspace_iter(&WORKSPACE, move |buffer: &mut Vec<u32>| {
+            buffer.clear();
+            buffer.extend_from_slice(values);
+            let mut idx = 0;
+            move |buffer: &mut Vec<u32>| {
+                let value = *buffer.get(idx)?;
+                idx += 1;
+                Some(2 * value)
+            }
+        })
+    }
+
+    let mut iter = doubled(&[1, 2, 3]);
+    assert_eq!(iter.next(), Some(2));
+    // The workspace variable remains usable, but the buffer is held by the iterator
+    with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| {
+        assert!(buffer.is_empty())
+    });
+    assert_eq!(iter.collect::<Vec<_>>(), [4, 6]);
+
+    let ptr = with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| {
+        assert_eq!(buffer, &[1, 2, 3]);
+        buffer.as_ptr()
+    });
+    assert_eq!(doubled(&[5]).collect::<Vec<_>>(), [10]);
+    with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| {
+        assert_eq!(buffer.as_ptr(), ptr)
+    });
+}
+
+#[test]
+fn workspace_error_covers_all_fallible_paths() {
+    define_thread_local_workspace!(WORKSPACE);
+
+    let reentrant = with_thread_local_workspace(&WORKSPACE, |_: &mut Vec<u32>| {
+        try_with_thread_local_workspace(&WORKSPACE, |_: &mut Vec<u32>| ()).unwrap_err()
+    });
+    assert_eq!(reentrant, WorkspaceError::Reentrant);
+    assert_eq!(
+        reentrant.to_string(),
+        "thread-local workspace is already in use on this thread"
+    );
+    assert_eq!(
+        try_with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| buffer.len()),
+        Ok(0)
+    );
+
+    let shared = SharedWorkspace::new();
+    let _guard = shared.get_or_default::<Vec<u32>>();
+    let contended = WorkspaceError::from(shared.try_get_or_default::<Vec<u32>>().unwrap_err());
+    assert_eq!(contended, WorkspaceError::Contended);
+    assert_eq!(contended.to_string(), "shared workspace is already in use");
+
+    let mut ws = Workspace::default();
+    let duplicate = ws.try_get_pair_mut::<u32, u32>().unwrap_err();
+    assert!(matches!(
+        duplicate,
+        WorkspaceError::DuplicateType { type_name: "u32" }
+    ));
+    assert_eq!(
+        duplicate.to_string(),
+        "can not mutably borrow an object of type u32 more than once"
+    );
+    assert!(ws.try_get_pair_mut::<u32, u64>().is_ok());
+
+    let allocation =
+        WorkspaceError::from(ws.try_get_slice_or_default::<u64>(usize::MAX).unwrap_err());
+    assert!(matches!(allocation, WorkspaceError::AllocationFailed(_)));
+    assert_eq!(
+        allocation.to_string(),
+        "failed to allocate memory for workspace object"
+    );
+
+    ws.freeze();
+    let frozen = WorkspaceError::from(ws.try_get_or_default::<String>().unwrap_err());
+    assert!(matches!(
+        frozen,
+        WorkspaceError::Frozen { type_name } if type_name.contains("String")
+    ));
+    assert!(frozen
+        .to_string()
+        .starts_with("can not insert an object of type"));
+
+    let mut bounded = BoundedWorkspace::<1>::new();
+    bounded.get_or_default::<u32>();
+    let limit = WorkspaceError::from(bounded.try_get_or_default::<u64>().unwrap_err());
+    assert_eq!(
+        limit,
+        WorkspaceError::TypeLimit {
+            max: 1,
+            type_name: "u64"
+        }
+    );
+    assert!(limit.to_string().ends_with("maximum of 1 distinct types"));
+}
+
+#[test]
+fn try_with_thread_local_workspace_applies_memory_policy() {
+    define_thread_local_workspace!(WORKSPACE);
+
+    with_thread_local_workspace_with_policy(
+        &WORKSPACE,
+        MemoryPolicy::RetainAtMost { max_capacity: 8 },
+        |_: &mut Vec<u32>| (),
+    );
+    let result = try_with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| {
+        buffer.extend(0..100);
+        buffer.clear();
+    });
+    assert_eq!(result, Ok(()));
+    let capacity =
+        with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| buffer.capacity());
+    assert!(capacity <= 8);
+}
+
+#[test]
+fn session_resets_on_each_get_and_shrinks_on_end() {
+    let mut ws = Workspace::default();
+    ws.get_or_default_with_policy::<Vec<u32>>(MemoryPolicy::ShrinkAfterUse { keep_ratio: 1.0 });
+    ws.get_or_insert_with_resetter(Vec::<u32>::new, Vec::clear);
+    // No resetter, so kept as is across gets
+    ws.get_or_default::<String>().push_str("kept");
+
+    {
+        let mut session = ws.session();
+        let buffer = session.get::<Vec<u32>>();
+        buffer.extend(0..1000);
+        let capacity = buffer.capacity();
+
+        let buffer = session.get::<Vec<u32>>();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.capacity(), capacity);
+        buffer.extend(0..10);
+        assert_eq!(session.get::<String>(), "kept");
+        // Access through the workspace itself does not reset
+        assert_eq!(session.try_get::<Vec<u32>>().unwrap().len(), 10);
+    }
+
+    let buffer = ws.try_get::<Vec<u32>>().unwrap();
+    assert_eq!(buffer.len(), 10);
+    assert!(buffer.capacity() < 1000);
+}
+
+#[test]
+fn session_get_looks_up_the_object_once() {
+    let accesses = Rc::new(RefCell::new(Vec::new()));
+    let mut ws = Workspace::default();
+    ws.get_or_insert_with_resetter(Vec::<u32>::new, Vec::clear);
+    let recorded = Rc::clone(&accesses);
+    ws.set_observer(Box::new(move |type_id, hit| {
+        recorded.borrow_mut().push((type_id, hit))
+    }));
+
+    {
+        let mut session = ws.session();
+        session.get::<Vec<u32>>().push(1);
+        assert!(session.get::<String>().is_empty());
+    }
+    assert_eq!(
+        *accesses.borrow(),
+        [
+            (TypeId::of::<Vec<u32>>(), true),
+            (TypeId::of::<String>(), false)
+        ]
+    );
+}
+
+#[test]
+fn get_exact_slice_or_default_has_deterministic_capacity() {
+    let mut ws = Workspace::default();
+    assert_eq!(ws.get_exact_slice_or_default::<u64>(10), &[0; 10]);
+    assert_eq!(ws.get_or_default::<Vec<u64>>().capacity(), 10);
+
+    // Reuse for a smaller length keeps the capacity
+    ws.get_exact_slice_or_default::<u64>(3)[0] = 1;
+    assert_eq!(ws.get_or_default::<Vec<u64>>().capacity(), 10);
+
+    // Amortized growth would at least double the capacity here
+    assert_eq!(
+        ws.get_exact_slice_or_default::<u64>(11),
+        &[1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(ws.get_or_default::<Vec<u64>>().capacity(), 11);
+}
+
+#[test]
+fn shared_workspace_guard_downgrades_to_read_guard() {
+    fn total(values: &[u32]) -> u32 {
+        values.iter().sum()
+    }
+
+    fn max(values: &[u32]) -> Option<u32> {
+        values.iter().copied().max()
+    }
+
+    let shared = SharedWorkspace::new();
+    {
+        let mut values = shared.get_or_default::<Vec<u32>>();
+        values.extend([4, 1, 3]);
+        let values = values.downgrade();
+        assert_eq!(total(&values), 8);
+        assert_eq!(max(&values), Some(4));
+        assert!(shared.is_in_use());
+    }
+    assert!(!shared.is_in_use());
+    assert_eq!(shared.with(|values: &mut Vec<u32>| values.len()), 3);
+}
+
+#[test]
+fn swap_in_ping_pongs_between_two_buffers() {
+    let mut ws = Workspace::default();
+    let mut current: Vec<u32> = vec![1, 2, 3];
+
+    assert!(ws.swap_in(Vec::<u32>::with_capacity(8)).is_empty());
+    let mut spare_capacity = None;
+    for _ in 0..2 {
+        // Take the spare buffer out, leaving an empty placeholder behind
+        let mut next = ws.swap_in(Vec::new());
+        spare_capacity.get_or_insert(next.capacity());
+        next.clear();
+        next.extend(current.iter().map(|x| x * 2));
+        let previous = std::mem::replace(&mut current, next);
+        assert!(ws.swap_in(previous).is_empty());
+    }
+
+    assert_eq!(current, [4, 8, 12]);
+    assert_eq!(spare_capacity, Some(8));
+    assert_eq!(ws.try_get::<Vec<u32>>().unwrap(), &[2, 4, 6]);
+}
+
+#[test]
+fn is_thread_local_workspace_borrowed_reports_use_on_this_thread() {
+    define_thread_local_workspace!(WORKSPACE);
+    define_thread_local_workspace!(OTHER_WORKSPACE);
+
+    assert!(!is_thread_local_workspace_borrowed(&WORKSPACE));
+    with_thread_local_workspace(&WORKSPACE, |_: &mut Vec<u32>| {
+        assert!(is_thread_local_workspace_borrowed(&WORKSPACE));
+        assert!(!is_thread_local_workspace_borrowed(&OTHER_WORKSPACE));
+    });
+    assert!(!is_thread_local_workspace_borrowed(&WORKSPACE));
+}
+
+#[test]
+fn prepare_uninit_reuses_capacity_and_reads_back_values() {
+    let mut ws = Workspace::default();
+
+    let uninit = ws.prepare_uninit::<u64>(100);
+    assert_eq!(uninit.len(), 100);
+    for (i, x) in uninit.iter_mut().enumerate() {
+        x.write(i as u64 * 3);
+    }
+    let ptr = uninit.as_ptr() as *const u64;
+    // SAFETY: All 100 elements were written above
+    let buffer = unsafe { ws.assume_init_slice::<u64>(100) };
+    assert_eq!(buffer.len(), 100);
+    assert!(buffer.iter().enumerate().all(|(i, &x)| x == i as u64 * 3));
+
+    let uninit = ws.prepare_uninit::<u64>(10);
+    assert_eq!(uninit.as_ptr() as *const u64, ptr);
+    for x in uninit.iter_mut() {
+        x.write(7);
+    }
+    // SAFETY: All 10 elements were written above
+    assert_eq!(unsafe { ws.assume_init_slice::<u64>(10) }, [7; 10]);
+    assert_eq!(ws.try_get::<Vec<u64>>().unwrap().as_ptr(), ptr);
+}
+
+#[test]
+fn prepare_uninit_drops_previous_contents() {
+    let mut ws = Workspace::default();
+    for round in 0..3 {
+        for (i, x) in ws.prepare_uninit::<String>(2).iter_mut().enumerate() {
+            x.write(format!("{}-{}", round, i));
+        }
+        // SAFETY: Both elements were written above
+        let strings = unsafe { ws.assume_init_slice::<String>(2) };
+        assert_eq!(strings, [format!("{}-0", round), format!("{}-1", round)]);
+    }
+    assert_eq!(ws.try_get::<Vec<String>>().unwrap().len(), 2);
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "warn-on-many-types")]
+
+use davenport::Workspace;
+use log::{Level, Log, Metadata, Record};
+use std::sync::Mutex;
+
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct RecordingLogger;
+
+impl Log for RecordingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            WARNINGS.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: RecordingLogger = RecordingLogger;
+
+#[derive(Default)]
+struct Buffer<const N: usize>;
+
+#[test]
+fn warning_is_logged_past_threshold() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Warn);
+
+    let mut ws = Workspace::default();
+    ws.set_many_types_threshold(3);
+    let _ = ws.get_or_default::<Buffer<0>>();
+    let _ = ws.get_or_default::<Buffer<1>>();
+    let _ = ws.get_or_default::<Buffer<2>>();
+    assert!(WARNINGS.lock().unwrap().is_empty());
+
+    let _ = ws.get_or_default::<Buffer<3>>();
+    let _ = ws.get_or_default::<Buffer<4>>();
+    let warnings = WARNINGS.lock().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("4 distinct types"));
+    #[cfg(feature = "debug-names")]
+    assert!(warnings[0].contains("Buffer<3>"));
+}
@@ -0,0 +1,30 @@
+//! Tests for targets without threads, on which `define_thread_local_workspace!` defines plain
+//! statics with the `wasm-single-thread` feature.
+//!
+//! Run with e.g. `cargo test --target wasm32-unknown-unknown --features wasm-single-thread`
+//! and a suitable test runner. The same code path is exercised on the host by
+//! `tests/single_threaded.rs`.
+#![cfg(all(
+    feature = "wasm-single-thread",
+    target_family = "wasm",
+    not(target_feature = "atomics")
+))]
+
+use davenport::{define_thread_local_workspace, with_thread_local_workspace, GlobalWorkspace};
+
+define_thread_local_workspace!(WORKSPACE);
+
+// The macro defines a global workspace instead of a thread-local variable
+const _: &GlobalWorkspace = &WORKSPACE;
+
+#[test]
+fn static_workspace_reuses_buffer() {
+    let ptr = with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| {
+        buffer.extend([1, 2, 3]);
+        buffer.as_ptr()
+    });
+    with_thread_local_workspace(&WORKSPACE, |buffer: &mut Vec<u32>| {
+        assert_eq!(buffer, &[1, 2, 3]);
+        assert_eq!(buffer.as_ptr(), ptr);
+    });
+}
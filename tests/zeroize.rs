@@ -0,0 +1,50 @@
+#![cfg(feature = "zeroize")]
+
+use davenport::Workspace;
+use std::cell::RefCell;
+use std::rc::Rc;
+use zeroize::Zeroize;
+
+#[test]
+fn reused_buffer_is_zeroized() {
+    let mut ws = Workspace::default();
+    let secret: &mut [u8; 16] = ws.get_or_default_zeroized();
+    assert_eq!(*secret, [0; 16]);
+    secret.fill(0xAB);
+
+    let reused: &mut [u8; 16] = ws.get_or_default_zeroized();
+    assert_eq!(*reused, [0; 16]);
+
+    let buffer: &mut Vec<u8> = ws.get_or_default_zeroized();
+    buffer.extend_from_slice(b"secret");
+    let buffer: &mut Vec<u8> = ws.get_or_default_zeroized();
+    assert!(buffer.is_empty());
+}
+
+#[derive(Default)]
+struct Recorder {
+    zeroized: Rc<RefCell<bool>>,
+}
+
+impl Zeroize for Recorder {
+    fn zeroize(&mut self) {
+        *self.zeroized.borrow_mut() = true;
+    }
+}
+
+#[test]
+fn entries_are_zeroized_on_clear_and_drop() {
+    let flag = Rc::new(RefCell::new(false));
+
+    let mut ws = Workspace::default();
+    ws.get_or_default_zeroized::<Recorder>().zeroized = Rc::clone(&flag);
+    ws.clear();
+    assert!(*flag.borrow());
+
+    *flag.borrow_mut() = false;
+    let mut ws = Workspace::default();
+    ws.get_or_default_zeroized::<Recorder>().zeroized = Rc::clone(&flag);
+    assert!(!*flag.borrow());
+    drop(ws);
+    assert!(*flag.borrow());
+}